@@ -1,2 +1,4 @@
 pub mod detector;
+#[cfg(feature = "emf-exporter")]
+pub mod metrics;
 pub mod trace;
@@ -0,0 +1,759 @@
+use async_trait::async_trait;
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::metrics::{
+    data::{self, ResourceMetrics},
+    exporter::PushMetricExporter,
+    MetricError, MetricResult, Temporality,
+};
+use serde_json::{json, Map};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum number of metrics in an EMF document.
+const MAX_METRICS_PER_DOCUMENT: usize = 100;
+/// Maximum number of dimensions in a dimension set.
+const MAX_DIMENSIONS: usize = 30;
+/// Member of EMF documents holding the metadata.
+const METADATA_KEY: &str = "_aws";
+
+/// Dimension sets reported for each metric, in addition to the set of all its attributes.
+///
+/// Each dimension set is a separate metric in CloudWatch, aggregated over the attributes
+/// not in the set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DimensionRollup {
+    /// Only report the set of all attributes.
+    #[default]
+    None,
+    /// Also report a set for each single attribute.
+    SingleDimension,
+    /// Also report the empty set and a set for each single attribute.
+    ZeroAndSingleDimension,
+}
+
+/// Exports metrics as [CloudWatch Embedded Metric Format] (EMF) JSON lines.
+///
+/// On AWS Lambda, Amazon ECS and other environments forwarding the process output to
+/// CloudWatch Logs, EMF documents written to stdout are extracted as CloudWatch metrics,
+/// without running an OpenTelemetry Collector or the CloudWatch agent.
+///
+/// Data points with the same attributes are written as one document, with the attributes as
+/// dimensions. Sums and gauges are written as values and histograms as statistic sets. As
+/// CloudWatch aggregates the values it receives, the exporter uses delta temporality.
+///
+/// Metrics with the same name, e.g. from different instrumentation scopes, are written to
+/// separate documents. A metric named like one of its attributes is written without that
+/// dimension. Metrics named `_aws`, attributes named `_aws` and non-finite values are not
+/// exported, as EMF cannot represent them.
+///
+/// ## Example
+///
+/// ```no_run
+/// use opentelemetry_aws::metrics::{DimensionRollup, EmfExporter};
+/// use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+/// use opentelemetry_sdk::runtime;
+///
+/// let exporter = EmfExporter::new("MyApplication")
+///     .with_dimension_rollup(DimensionRollup::ZeroAndSingleDimension);
+/// let reader = PeriodicReader::builder(exporter, runtime::Tokio).build();
+/// let _provider = SdkMeterProvider::builder().with_reader(reader).build();
+/// ```
+///
+/// [CloudWatch Embedded Metric Format]: https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/CloudWatch_Embedded_Metric_Format_Specification.html
+pub struct EmfExporter {
+    namespace: String,
+    dimension_rollup: DimensionRollup,
+    high_resolution: bool,
+    writer: Mutex<Box<dyn Write + Send>>,
+    is_shutdown: AtomicBool,
+}
+
+impl EmfExporter {
+    /// Create an exporter writing metrics of the given CloudWatch namespace to stdout.
+    pub fn new(namespace: impl Into<String>) -> Self {
+        EmfExporter {
+            namespace: namespace.into(),
+            dimension_rollup: DimensionRollup::default(),
+            high_resolution: false,
+            writer: Mutex::new(Box::new(io::stdout())),
+            is_shutdown: AtomicBool::new(false),
+        }
+    }
+
+    /// Write the EMF documents to the given writer instead of stdout, e.g. a [`std::fs::File`]
+    /// created from a file descriptor.
+    pub fn with_writer(mut self, writer: impl Write + Send + 'static) -> Self {
+        self.writer = Mutex::new(Box::new(writer));
+        self
+    }
+
+    /// Set the dimension sets reported for each metric. Defaults to [`DimensionRollup::None`].
+    pub fn with_dimension_rollup(mut self, dimension_rollup: DimensionRollup) -> Self {
+        self.dimension_rollup = dimension_rollup;
+        self
+    }
+
+    /// Store metrics with a one second resolution instead of one minute.
+    /// High resolution metrics are charged at a higher rate by CloudWatch.
+    pub fn with_high_resolution(mut self, high_resolution: bool) -> Self {
+        self.high_resolution = high_resolution;
+        self
+    }
+
+    /// Returns the EMF documents for the metrics, one per line.
+    fn documents(&self, metrics: &ResourceMetrics) -> Vec<String> {
+        // Group the data points by attributes, ordering the attributes by key.
+        let mut groups: BTreeMap<Vec<(String, String)>, Group> = BTreeMap::new();
+        for scope_metrics in &metrics.scope_metrics {
+            for metric in &scope_metrics.metrics {
+                if metric.name == METADATA_KEY {
+                    continue;
+                }
+                for point in data_points(metric.data.as_ref()) {
+                    // Dimensions and metric values share the members of the document.
+                    let mut dimensions: Vec<(String, String)> = point
+                        .attributes
+                        .iter()
+                        .filter(|kv| {
+                            kv.key.as_str() != METADATA_KEY && kv.key.as_str() != metric.name
+                        })
+                        .map(|kv| (kv.key.to_string(), kv.value.as_str().into_owned()))
+                        .collect();
+                    dimensions.sort();
+                    dimensions.dedup_by(|a, b| a.0 == b.0);
+                    let group = groups.entry(dimensions).or_default();
+                    group.time = group.time.max(point.time);
+                    group.metrics.push(EmfMetric {
+                        name: metric.name.to_string(),
+                        unit: cloudwatch_unit(&metric.unit),
+                        value: point.value,
+                    });
+                }
+            }
+        }
+
+        let mut documents = Vec::new();
+        for (dimensions, group) in groups {
+            let dimension_sets = dimension_sets(self.dimension_rollup, &dimensions);
+            for metrics in split_documents(&group.metrics) {
+                let mut document = Map::new();
+                for (key, value) in &dimensions {
+                    document.insert(key.clone(), json!(value));
+                }
+                let mut definitions = Vec::with_capacity(metrics.len());
+                for metric in metrics {
+                    let mut definition = Map::new();
+                    definition.insert("Name".into(), json!(metric.name));
+                    if let Some(unit) = metric.unit {
+                        definition.insert("Unit".into(), json!(unit));
+                    }
+                    if self.high_resolution {
+                        definition.insert("StorageResolution".into(), json!(1));
+                    }
+                    definitions.push(definition);
+                    document.insert(metric.name.clone(), metric.value.clone());
+                }
+                document.insert(
+                    METADATA_KEY.into(),
+                    json!({
+                        "Timestamp": unix_millis(group.time),
+                        "CloudWatchMetrics": [{
+                            "Namespace": self.namespace,
+                            "Dimensions": dimension_sets,
+                            "Metrics": definitions,
+                        }],
+                    }),
+                );
+                documents.push(serde_json::Value::Object(document).to_string());
+            }
+        }
+        documents
+    }
+}
+
+/// Splits the metrics of a group into documents of at most [`MAX_METRICS_PER_DOCUMENT`]
+/// metrics with distinct names.
+fn split_documents(metrics: &[EmfMetric]) -> Vec<Vec<&EmfMetric>> {
+    let mut documents: Vec<Vec<&EmfMetric>> = Vec::new();
+    for metric in metrics {
+        let document = documents.iter_mut().find(|document| {
+            document.len() < MAX_METRICS_PER_DOCUMENT
+                && document.iter().all(|other| other.name != metric.name)
+        });
+        match document {
+            Some(document) => document.push(metric),
+            None => documents.push(vec![metric]),
+        }
+    }
+    documents
+}
+
+/// Returns the dimension sets of a document with the given dimensions.
+fn dimension_sets(rollup: DimensionRollup, dimensions: &[(String, String)]) -> Vec<Vec<&str>> {
+    let keys: Vec<&str> = dimensions
+        .iter()
+        .take(MAX_DIMENSIONS)
+        .map(|(key, _)| key.as_str())
+        .collect();
+    let mut sets = vec![keys.clone()];
+    if rollup == DimensionRollup::ZeroAndSingleDimension && !keys.is_empty() {
+        sets.push(Vec::new());
+    }
+    if rollup != DimensionRollup::None && keys.len() > 1 {
+        sets.extend(keys.iter().map(|key| vec![*key]));
+    }
+    sets
+}
+
+#[derive(Default)]
+struct Group {
+    time: Option<SystemTime>,
+    metrics: Vec<EmfMetric>,
+}
+
+struct EmfMetric {
+    name: String,
+    unit: Option<&'static str>,
+    value: serde_json::Value,
+}
+
+struct EmfDataPoint<'a> {
+    attributes: &'a [KeyValue],
+    time: Option<SystemTime>,
+    value: serde_json::Value,
+}
+
+/// Returns the data points of the aggregation, with histograms as EMF statistic sets.
+fn data_points(data: &dyn data::Aggregation) -> Vec<EmfDataPoint<'_>> {
+    let data = data.as_any();
+    if let Some(sum) = data.downcast_ref::<data::Sum<u64>>() {
+        scalar_data_points(&sum.data_points)
+    } else if let Some(sum) = data.downcast_ref::<data::Sum<i64>>() {
+        scalar_data_points(&sum.data_points)
+    } else if let Some(sum) = data.downcast_ref::<data::Sum<f64>>() {
+        scalar_data_points(&sum.data_points)
+    } else if let Some(gauge) = data.downcast_ref::<data::Gauge<u64>>() {
+        scalar_data_points(&gauge.data_points)
+    } else if let Some(gauge) = data.downcast_ref::<data::Gauge<i64>>() {
+        scalar_data_points(&gauge.data_points)
+    } else if let Some(gauge) = data.downcast_ref::<data::Gauge<f64>>() {
+        scalar_data_points(&gauge.data_points)
+    } else if let Some(histogram) = data.downcast_ref::<data::Histogram<u64>>() {
+        histogram_data_points(&histogram.data_points)
+    } else if let Some(histogram) = data.downcast_ref::<data::Histogram<f64>>() {
+        histogram_data_points(&histogram.data_points)
+    } else if let Some(histogram) = data.downcast_ref::<data::ExponentialHistogram<u64>>() {
+        exponential_histogram_data_points(&histogram.data_points)
+    } else if let Some(histogram) = data.downcast_ref::<data::ExponentialHistogram<f64>>() {
+        exponential_histogram_data_points(&histogram.data_points)
+    } else {
+        Vec::new()
+    }
+}
+
+fn scalar_data_points<T>(data_points: &[data::DataPoint<T>]) -> Vec<EmfDataPoint<'_>>
+where
+    T: Copy + Into<serde_json::Value>,
+{
+    data_points
+        .iter()
+        .filter_map(|point| {
+            // Non-finite floats are converted to null, which is not a valid metric value.
+            let value: serde_json::Value = point.value.into();
+            (!value.is_null()).then_some(EmfDataPoint {
+                attributes: &point.attributes,
+                time: point.time,
+                value,
+            })
+        })
+        .collect()
+}
+
+fn histogram_data_points<T>(data_points: &[data::HistogramDataPoint<T>]) -> Vec<EmfDataPoint<'_>>
+where
+    T: HistogramValue,
+{
+    data_points
+        .iter()
+        .filter(|point| point.count > 0)
+        .filter_map(|point| {
+            Some(EmfDataPoint {
+                attributes: &point.attributes,
+                time: Some(point.time),
+                value: statistic_set(
+                    point.count,
+                    point.sum.as_f64(),
+                    point.min.map(T::as_f64),
+                    point.max.map(T::as_f64),
+                )?,
+            })
+        })
+        .collect()
+}
+
+fn exponential_histogram_data_points<T>(
+    data_points: &[data::ExponentialHistogramDataPoint<T>],
+) -> Vec<EmfDataPoint<'_>>
+where
+    T: HistogramValue,
+{
+    data_points
+        .iter()
+        .filter(|point| point.count > 0)
+        .filter_map(|point| {
+            Some(EmfDataPoint {
+                attributes: &point.attributes,
+                time: Some(point.time),
+                value: statistic_set(
+                    point.count as u64,
+                    point.sum.as_f64(),
+                    point.min.map(T::as_f64),
+                    point.max.map(T::as_f64),
+                )?,
+            })
+        })
+        .collect()
+}
+
+trait HistogramValue: Copy {
+    fn as_f64(self) -> f64;
+}
+
+impl HistogramValue for u64 {
+    fn as_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl HistogramValue for f64 {
+    fn as_f64(self) -> f64 {
+        self
+    }
+}
+
+/// Returns the statistic set of a histogram, or `None` if any statistic is not finite.
+/// CloudWatch requires the minimum and maximum, the mean is used for histograms recorded
+/// without them.
+fn statistic_set(
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Option<serde_json::Value> {
+    let mean = sum / count as f64;
+    let (min, max) = (min.unwrap_or(mean), max.unwrap_or(mean));
+    if !(sum.is_finite() && min.is_finite() && max.is_finite()) {
+        return None;
+    }
+    Some(json!({
+        "Count": count,
+        "Sum": sum,
+        "Min": min,
+        "Max": max,
+    }))
+}
+
+/// Maps UCUM units to CloudWatch units. Units without an equivalent are omitted.
+fn cloudwatch_unit(unit: &str) -> Option<&'static str> {
+    match unit {
+        "s" => Some("Seconds"),
+        "ms" => Some("Milliseconds"),
+        "us" => Some("Microseconds"),
+        "By" => Some("Bytes"),
+        "kBy" => Some("Kilobytes"),
+        "MBy" => Some("Megabytes"),
+        "GBy" => Some("Gigabytes"),
+        "TBy" => Some("Terabytes"),
+        "bit" => Some("Bits"),
+        "By/s" => Some("Bytes/Second"),
+        "bit/s" => Some("Bits/Second"),
+        "%" => Some("Percent"),
+        "1" => Some("None"),
+        _ if unit.starts_with('{') && unit.ends_with('}') => Some("Count"),
+        _ => None,
+    }
+}
+
+fn unix_millis(time: Option<SystemTime>) -> u128 {
+    time.unwrap_or_else(SystemTime::now)
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default()
+}
+
+impl fmt::Debug for EmfExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EmfExporter")
+            .field("namespace", &self.namespace)
+            .field("dimension_rollup", &self.dimension_rollup)
+            .field("high_resolution", &self.high_resolution)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl PushMetricExporter for EmfExporter {
+    async fn export(&self, metrics: &mut ResourceMetrics) -> MetricResult<()> {
+        if self.is_shutdown.load(Ordering::Relaxed) {
+            return Err(MetricError::Other("exporter is shut down".into()));
+        }
+        let documents = self.documents(metrics);
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let mut writer = self.writer.lock()?;
+        for document in documents {
+            writeln!(writer, "{}", document).map_err(|err| MetricError::Other(err.to_string()))?;
+        }
+        writer
+            .flush()
+            .map_err(|err| MetricError::Other(err.to_string()))
+    }
+
+    async fn force_flush(&self) -> MetricResult<()> {
+        self.writer
+            .lock()?
+            .flush()
+            .map_err(|err| MetricError::Other(err.to_string()))
+    }
+
+    fn shutdown(&self) -> MetricResult<()> {
+        self.is_shutdown.store(true, Ordering::Relaxed);
+        self.writer
+            .lock()?
+            .flush()
+            .map_err(|err| MetricError::Other(err.to_string()))
+    }
+
+    fn temporality(&self) -> Temporality {
+        Temporality::Delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::InstrumentationScope;
+    use opentelemetry_sdk::metrics::data::{Metric, ScopeMetrics};
+    use opentelemetry_sdk::Resource;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn metric(name: &'static str, unit: &'static str, data: Box<dyn data::Aggregation>) -> Metric {
+        Metric {
+            name: name.into(),
+            description: "".into(),
+            unit: unit.into(),
+            data,
+        }
+    }
+
+    fn resource_metrics(metrics: Vec<Metric>) -> ResourceMetrics {
+        ResourceMetrics {
+            resource: Resource::empty(),
+            scope_metrics: vec![ScopeMetrics {
+                scope: InstrumentationScope::builder("test").build(),
+                metrics,
+            }],
+        }
+    }
+
+    fn sum(attributes: Vec<KeyValue>, value: u64) -> Box<dyn data::Aggregation> {
+        Box::new(data::Sum {
+            data_points: vec![data::DataPoint {
+                attributes,
+                start_time: None,
+                time: Some(time()),
+                value,
+                exemplars: vec![],
+            }],
+            temporality: Temporality::Delta,
+            is_monotonic: true,
+        })
+    }
+
+    fn parse(documents: Vec<String>) -> Vec<serde_json::Value> {
+        documents
+            .iter()
+            .map(|document| serde_json::from_str(document).unwrap())
+            .collect()
+    }
+
+    fn time() -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+    }
+
+    #[tokio::test]
+    async fn test_export() {
+        let buffer = SharedBuffer::default();
+        let exporter = EmfExporter::new("Test").with_writer(buffer.clone());
+        let attributes = vec![KeyValue::new("route", "/"), KeyValue::new("method", "GET")];
+        let mut metrics = resource_metrics(vec![
+            metric(
+                "requests",
+                "{request}",
+                Box::new(data::Sum {
+                    data_points: vec![data::DataPoint {
+                        attributes: attributes.clone(),
+                        start_time: None,
+                        time: Some(time()),
+                        value: 3u64,
+                        exemplars: vec![],
+                    }],
+                    temporality: Temporality::Delta,
+                    is_monotonic: true,
+                }),
+            ),
+            metric(
+                "duration",
+                "ms",
+                Box::new(data::Histogram {
+                    data_points: vec![data::HistogramDataPoint {
+                        attributes,
+                        start_time: UNIX_EPOCH,
+                        time: time(),
+                        count: 2,
+                        bounds: vec![10.0],
+                        bucket_counts: vec![1, 1],
+                        min: Some(5.0),
+                        max: Some(20.0),
+                        sum: 25.0,
+                        exemplars: vec![],
+                    }],
+                    temporality: Temporality::Delta,
+                }),
+            ),
+        ]);
+
+        exporter.export(&mut metrics).await.unwrap();
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let documents: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            documents,
+            vec![json!({
+                "_aws": {
+                    "Timestamp": 1_700_000_000_123u64,
+                    "CloudWatchMetrics": [{
+                        "Namespace": "Test",
+                        "Dimensions": [["method", "route"]],
+                        "Metrics": [
+                            {"Name": "requests", "Unit": "Count"},
+                            {"Name": "duration", "Unit": "Milliseconds"},
+                        ],
+                    }],
+                },
+                "method": "GET",
+                "route": "/",
+                "requests": 3,
+                "duration": {"Count": 2, "Sum": 25.0, "Min": 5.0, "Max": 20.0},
+            })]
+        );
+
+        exporter.shutdown().unwrap();
+        assert!(exporter.export(&mut metrics).await.is_err());
+    }
+
+    #[test]
+    fn test_high_resolution() {
+        let exporter = EmfExporter::new("Test").with_high_resolution(true);
+        let metrics = resource_metrics(vec![metric(
+            "temperature",
+            "",
+            Box::new(data::Gauge {
+                data_points: vec![data::DataPoint {
+                    attributes: vec![],
+                    start_time: None,
+                    time: Some(time()),
+                    value: -1.5f64,
+                    exemplars: vec![],
+                }],
+            }),
+        )]);
+
+        let documents = exporter.documents(&metrics);
+        assert_eq!(documents.len(), 1);
+        let document: serde_json::Value = serde_json::from_str(&documents[0]).unwrap();
+        assert_eq!(document["temperature"], json!(-1.5));
+        assert_eq!(
+            document["_aws"]["CloudWatchMetrics"][0]["Dimensions"],
+            json!([[]])
+        );
+        assert_eq!(
+            document["_aws"]["CloudWatchMetrics"][0]["Metrics"],
+            json!([{"Name": "temperature", "StorageResolution": 1}])
+        );
+    }
+
+    #[test]
+    fn test_metric_named_like_dimension() {
+        let exporter = EmfExporter::new("Test");
+        let attributes = vec![KeyValue::new("route", "/")];
+        let metrics = resource_metrics(vec![
+            metric("requests", "", sum(attributes.clone(), 1)),
+            metric("route", "", sum(attributes, 2)),
+        ]);
+
+        let documents = parse(exporter.documents(&metrics));
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0]["route"], json!(2));
+        assert_eq!(
+            documents[0]["_aws"]["CloudWatchMetrics"][0]["Dimensions"],
+            json!([[]])
+        );
+        assert_eq!(documents[1]["route"], json!("/"));
+        assert_eq!(documents[1]["requests"], json!(1));
+    }
+
+    #[test]
+    fn test_metadata_key_not_exported() {
+        let exporter = EmfExporter::new("Test");
+        let metrics = resource_metrics(vec![
+            metric("_aws", "", sum(vec![], 1)),
+            metric("requests", "", sum(vec![KeyValue::new("_aws", "value")], 2)),
+        ]);
+
+        let documents = parse(exporter.documents(&metrics));
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0]["requests"], json!(2));
+        assert_eq!(
+            documents[0]["_aws"]["CloudWatchMetrics"][0]["Dimensions"],
+            json!([[]])
+        );
+    }
+
+    #[test]
+    fn test_same_metric_name_in_scopes() {
+        let exporter = EmfExporter::new("Test");
+        let scope_metrics = |scope: &'static str, value| ScopeMetrics {
+            scope: InstrumentationScope::builder(scope).build(),
+            metrics: vec![metric("requests", "", sum(vec![], value))],
+        };
+        let metrics = ResourceMetrics {
+            resource: Resource::empty(),
+            scope_metrics: vec![scope_metrics("a", 1), scope_metrics("b", 2)],
+        };
+
+        let documents = parse(exporter.documents(&metrics));
+        assert_eq!(documents.len(), 2);
+        for (document, value) in documents.iter().zip([1, 2]) {
+            assert_eq!(document["requests"], json!(value));
+            assert_eq!(
+                document["_aws"]["CloudWatchMetrics"][0]["Metrics"],
+                json!([{"Name": "requests"}])
+            );
+        }
+    }
+
+    #[test]
+    fn test_non_finite_values_not_exported() {
+        let exporter = EmfExporter::new("Test");
+        let metrics = resource_metrics(vec![
+            metric(
+                "temperature",
+                "",
+                Box::new(data::Gauge {
+                    data_points: vec![data::DataPoint {
+                        attributes: vec![],
+                        start_time: None,
+                        time: Some(time()),
+                        value: f64::NAN,
+                        exemplars: vec![],
+                    }],
+                }),
+            ),
+            metric(
+                "duration",
+                "",
+                Box::new(data::Histogram {
+                    data_points: vec![data::HistogramDataPoint {
+                        attributes: vec![],
+                        start_time: UNIX_EPOCH,
+                        time: time(),
+                        count: 1,
+                        bounds: vec![],
+                        bucket_counts: vec![1],
+                        min: None,
+                        max: None,
+                        sum: f64::INFINITY,
+                        exemplars: vec![],
+                    }],
+                    temporality: Temporality::Delta,
+                }),
+            ),
+        ]);
+
+        assert!(exporter.documents(&metrics).is_empty());
+    }
+
+    #[test]
+    fn test_split_documents() {
+        let metric = |name: &str| EmfMetric {
+            name: name.into(),
+            unit: None,
+            value: json!(1),
+        };
+        let mut metrics: Vec<EmfMetric> = (0..MAX_METRICS_PER_DOCUMENT)
+            .map(|i| metric(&i.to_string()))
+            .collect();
+        metrics.push(metric("0"));
+        metrics.push(metric("last"));
+
+        let documents = split_documents(&metrics);
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].len(), MAX_METRICS_PER_DOCUMENT);
+        let names: Vec<&str> = documents[1].iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["0", "last"]);
+    }
+
+    #[test]
+    fn test_dimension_sets() {
+        let dimensions = vec![
+            ("a".to_string(), "1".to_string()),
+            ("b".to_string(), "2".to_string()),
+        ];
+        assert_eq!(
+            dimension_sets(DimensionRollup::None, &dimensions),
+            vec![vec!["a", "b"]]
+        );
+        assert_eq!(
+            dimension_sets(DimensionRollup::SingleDimension, &dimensions),
+            vec![vec!["a", "b"], vec!["a"], vec!["b"]]
+        );
+        assert_eq!(
+            dimension_sets(DimensionRollup::ZeroAndSingleDimension, &dimensions),
+            vec![vec!["a", "b"], vec![], vec!["a"], vec!["b"]]
+        );
+        assert_eq!(
+            dimension_sets(DimensionRollup::ZeroAndSingleDimension, &[]),
+            vec![Vec::<&str>::new()]
+        );
+    }
+
+    #[test]
+    fn test_cloudwatch_unit() {
+        assert_eq!(cloudwatch_unit("s"), Some("Seconds"));
+        assert_eq!(cloudwatch_unit("{request}"), Some("Count"));
+        assert_eq!(cloudwatch_unit("m"), None);
+    }
+}
@@ -0,0 +1,5 @@
+#[cfg(feature = "emf-exporter")]
+pub mod emf_exporter;
+
+#[cfg(feature = "emf-exporter")]
+pub use emf_exporter::{DimensionRollup, EmfExporter};
@@ -1,5 +1,7 @@
 #[cfg(feature = "trace")]
 pub mod id_generator;
+#[cfg(feature = "xray-exporter")]
+pub mod xray_exporter;
 #[cfg(feature = "trace")]
 pub mod xray_propagator;
 
@@ -8,3 +10,6 @@ pub use xray_propagator::XrayPropagator;
 
 #[cfg(feature = "trace")]
 pub use id_generator::XrayIdGenerator;
+
+#[cfg(feature = "xray-exporter")]
+pub use xray_exporter::XrayExporter;
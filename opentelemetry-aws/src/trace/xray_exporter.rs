@@ -0,0 +1,476 @@
+use opentelemetry::{
+    trace::{SpanId, SpanKind, Status, TraceError, TraceId},
+    Array, Value,
+};
+use opentelemetry_sdk::{
+    export::trace::{ExportResult, SpanData, SpanExporter},
+    Resource,
+};
+use serde_json::{json, Map};
+use std::fmt;
+use std::future::{ready, Future};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::pin::Pin;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAEMON_ADDRESS_ENV: &str = "AWS_XRAY_DAEMON_ADDRESS";
+const DEFAULT_DAEMON_ADDRESS: &str = "127.0.0.1:2000";
+const DAEMON_HEADER: &str = "{\"format\": \"json\", \"version\": 1}\n";
+
+const MAX_NAME_LENGTH: usize = 200;
+/// Maximum payload of an IPv4 UDP datagram.
+const MAX_MESSAGE_SIZE: usize = 65_507;
+
+const SERVICE_NAME: &str = "service.name";
+const HTTP_REQUEST_METHOD: &str = "http.request.method";
+const HTTP_RESPONSE_STATUS_CODE: &str = "http.response.status_code";
+const URL_FULL: &str = "url.full";
+const RPC_SYSTEM: &str = "rpc.system";
+
+/// Exports spans as X-Ray segment documents to the [X-Ray daemon] over UDP.
+///
+/// This lets applications send traces to X-Ray without running an OpenTelemetry Collector.
+/// Root spans and spans of kind [`SpanKind::Server`] or [`SpanKind::Consumer`] are sent as
+/// segments, named after the `service.name` resource attribute. All other spans are sent as
+/// independent subsegments of their parent.
+///
+/// X-Ray only accepts trace ids starting with a recent timestamp, so the tracer provider
+/// must generate ids with [`XrayIdGenerator`]. Span attributes are recorded as segment
+/// metadata, HTTP attributes are additionally mapped to the segment's `http` object.
+///
+/// ## Example
+///
+/// ```no_run
+/// use opentelemetry_aws::trace::{XrayExporter, XrayIdGenerator};
+/// use opentelemetry_sdk::trace::{self, TracerProvider};
+///
+/// let exporter = XrayExporter::new().expect("failed to create the X-Ray exporter");
+/// let _provider: TracerProvider = TracerProvider::builder()
+///     .with_config(trace::config().with_id_generator(XrayIdGenerator::default()))
+///     .with_simple_exporter(exporter)
+///     .build();
+/// ```
+///
+/// [X-Ray daemon]: https://docs.aws.amazon.com/xray/latest/devguide/xray-daemon.html
+/// [`XrayIdGenerator`]: crate::trace::XrayIdGenerator
+pub struct XrayExporter {
+    socket: UdpSocket,
+    daemon_address: SocketAddr,
+    service_name: Option<String>,
+}
+
+impl XrayExporter {
+    /// Create an exporter sending to the daemon address from the `AWS_XRAY_DAEMON_ADDRESS`
+    /// environment variable, or `127.0.0.1:2000` if it is not set.
+    pub fn new() -> io::Result<Self> {
+        let address = std::env::var(DAEMON_ADDRESS_ENV)
+            .ok()
+            .and_then(|address| parse_daemon_address(&address))
+            .unwrap_or_else(|| DEFAULT_DAEMON_ADDRESS.to_string());
+        let daemon_address = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("X-Ray daemon address {} could not be resolved", address),
+            )
+        })?;
+        Self::with_daemon_address(daemon_address)
+    }
+
+    /// Create an exporter sending to the daemon at the given address.
+    pub fn with_daemon_address(daemon_address: SocketAddr) -> io::Result<Self> {
+        let bind_address: SocketAddr = if daemon_address.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        Ok(XrayExporter {
+            socket: UdpSocket::bind(bind_address)?,
+            daemon_address,
+            service_name: None,
+        })
+    }
+
+    fn send(&self, span: &SpanData) -> io::Result<()> {
+        let message = daemon_message(span, self.service_name.as_deref())?;
+        self.socket
+            .send_to(message.as_bytes(), self.daemon_address)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for XrayExporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("XrayExporter")
+            .field("daemon_address", &self.daemon_address)
+            .field("service_name", &self.service_name)
+            .finish()
+    }
+}
+
+impl SpanExporter for XrayExporter {
+    fn export(
+        &mut self,
+        batch: Vec<SpanData>,
+    ) -> Pin<Box<dyn Future<Output = ExportResult> + Send + 'static>> {
+        // Each span is sent in its own datagram, so one oversized document only loses itself.
+        let mut errors = Vec::new();
+        for span in &batch {
+            if let Err(err) = self.send(span) {
+                errors.push(err.to_string());
+            }
+        }
+        let result = if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(TraceError::Other(
+                format!(
+                    "failed to send {} X-Ray documents: {}",
+                    errors.len(),
+                    errors.join("; ")
+                )
+                .into(),
+            ))
+        };
+        Box::pin(ready(result))
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.service_name = resource
+            .get(SERVICE_NAME.into())
+            .map(|name| name.as_str().into_owned());
+    }
+}
+
+/// Returns the datagram sent to the daemon for the span. Documents exceeding the datagram size
+/// limit are sent without their metadata, or not at all if they still do not fit.
+fn daemon_message(span: &SpanData, service_name: Option<&str>) -> io::Result<String> {
+    let mut document = segment_document(span, service_name);
+    let message = format!("{}{}", DAEMON_HEADER, document);
+    if message.len() <= MAX_MESSAGE_SIZE {
+        return Ok(message);
+    }
+    if let Some(document) = document.as_object_mut() {
+        document.remove("metadata");
+    }
+    let message = format!("{}{}", DAEMON_HEADER, document);
+    if message.len() <= MAX_MESSAGE_SIZE {
+        return Ok(message);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "X-Ray document of span {} is {} bytes without metadata, exceeding the {} bytes datagram limit",
+            span.span_context.span_id(),
+            message.len(),
+            MAX_MESSAGE_SIZE
+        ),
+    ))
+}
+
+/// Returns the UDP address of an `AWS_XRAY_DAEMON_ADDRESS` value, which is either a single
+/// address or separate addresses like `tcp:127.0.0.1:2000 udp:127.0.0.2:2001`.
+fn parse_daemon_address(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if !value.contains(' ') {
+        return Some(value.to_string());
+    }
+    value
+        .split_whitespace()
+        .find_map(|address| address.strip_prefix("udp:"))
+        .map(str::to_string)
+}
+
+/// Converts an OpenTelemetry trace id into the X-Ray format `1-{8 hex digits}-{24 hex digits}`.
+fn xray_trace_id(trace_id: TraceId) -> String {
+    let hex = trace_id.to_string();
+    format!("1-{}-{}", &hex[..8], &hex[8..])
+}
+
+fn is_segment(span: &SpanData) -> bool {
+    span.parent_span_id == SpanId::INVALID
+        || matches!(span.span_kind, SpanKind::Server | SpanKind::Consumer)
+}
+
+fn segment_document(span: &SpanData, service_name: Option<&str>) -> serde_json::Value {
+    let is_segment = is_segment(span);
+    let name = match service_name {
+        Some(service_name) if is_segment => service_name,
+        _ => span.name.as_ref(),
+    };
+
+    let mut document = Map::new();
+    document.insert(
+        "name".into(),
+        name.chars()
+            .take(MAX_NAME_LENGTH)
+            .collect::<String>()
+            .into(),
+    );
+    document.insert("id".into(), span.span_context.span_id().to_string().into());
+    document.insert(
+        "trace_id".into(),
+        xray_trace_id(span.span_context.trace_id()).into(),
+    );
+    document.insert("start_time".into(), epoch_seconds(span.start_time).into());
+    document.insert("end_time".into(), epoch_seconds(span.end_time).into());
+    if span.parent_span_id != SpanId::INVALID {
+        document.insert("parent_id".into(), span.parent_span_id.to_string().into());
+    }
+    if !is_segment {
+        document.insert("type".into(), "subsegment".into());
+        let is_aws_api = matches!(
+            get_attribute(span, RPC_SYSTEM),
+            Some(Value::String(system)) if system.as_str() == "aws-api"
+        );
+        let namespace = if is_aws_api {
+            Some("aws")
+        } else if matches!(span.span_kind, SpanKind::Client | SpanKind::Producer) {
+            Some("remote")
+        } else {
+            None
+        };
+        if let Some(namespace) = namespace {
+            document.insert("namespace".into(), namespace.into());
+        }
+    }
+
+    let status_code = match get_attribute(span, HTTP_RESPONSE_STATUS_CODE) {
+        Some(Value::I64(status_code)) => Some(*status_code),
+        _ => None,
+    };
+    match status_code {
+        Some(429) => {
+            document.insert("error".into(), true.into());
+            document.insert("throttle".into(), true.into());
+        }
+        Some(400..=499) => {
+            document.insert("error".into(), true.into());
+        }
+        Some(500..=599) => {
+            document.insert("fault".into(), true.into());
+        }
+        _ if matches!(span.status, Status::Error { .. }) => {
+            document.insert("fault".into(), true.into());
+        }
+        _ => {}
+    }
+
+    let mut request = Map::new();
+    if let Some(method) = get_attribute(span, HTTP_REQUEST_METHOD) {
+        request.insert("method".into(), method.as_str().into());
+    }
+    if let Some(url) = get_attribute(span, URL_FULL) {
+        request.insert("url".into(), url.as_str().into());
+    }
+    let mut http = Map::new();
+    if !request.is_empty() {
+        http.insert("request".into(), request.into());
+    }
+    if let Some(status_code) = status_code {
+        http.insert("response".into(), json!({ "status": status_code }));
+    }
+    if !http.is_empty() {
+        document.insert("http".into(), http.into());
+    }
+
+    if !span.attributes.is_empty() {
+        let attributes: Map<String, serde_json::Value> = span
+            .attributes
+            .iter()
+            .map(|kv| (kv.key.to_string(), to_json(&kv.value)))
+            .collect();
+        document.insert("metadata".into(), json!({ "default": attributes }));
+    }
+
+    document.into()
+}
+
+fn get_attribute<'a>(span: &'a SpanData, key: &str) -> Option<&'a Value> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+fn epoch_seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default()
+}
+
+fn to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(b) => (*b).into(),
+        Value::I64(i) => (*i).into(),
+        Value::F64(f) => (*f).into(),
+        Value::String(s) => s.as_str().into(),
+        Value::Array(Array::Bool(values)) => values.as_slice().into(),
+        Value::Array(Array::I64(values)) => values.as_slice().into(),
+        Value::Array(Array::F64(values)) => values.as_slice().into(),
+        Value::Array(Array::String(values)) => values
+            .iter()
+            .map(|value| serde_json::Value::from(value.as_str()))
+            .collect(),
+        other => other.to_string().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanContext, TraceFlags, TraceState};
+    use opentelemetry::KeyValue;
+    use opentelemetry_sdk::trace::{SpanEvents, SpanLinks};
+    use std::time::Duration;
+
+    fn span_data(
+        parent_span_id: SpanId,
+        span_kind: SpanKind,
+        attributes: Vec<KeyValue>,
+    ) -> SpanData {
+        SpanData {
+            span_context: SpanContext::new(
+                TraceId::from_hex("5759e988bd862e3fe1be46a994272793").unwrap(),
+                SpanId::from_hex("53995c3f42cd8ad8").unwrap(),
+                TraceFlags::SAMPLED,
+                false,
+                TraceState::default(),
+            ),
+            parent_span_id,
+            span_kind,
+            name: "GET /orders".into(),
+            start_time: UNIX_EPOCH + Duration::from_millis(1_478_293_361_271),
+            end_time: UNIX_EPOCH + Duration::from_millis(1_478_293_361_449),
+            attributes,
+            dropped_attributes_count: 0,
+            events: SpanEvents::default(),
+            links: SpanLinks::default(),
+            status: Status::Unset,
+            instrumentation_scope: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_segment_document() {
+        let span = span_data(
+            SpanId::INVALID,
+            SpanKind::Server,
+            vec![
+                KeyValue::new(HTTP_REQUEST_METHOD, "GET"),
+                KeyValue::new(URL_FULL, "https://example.com/orders"),
+                KeyValue::new(HTTP_RESPONSE_STATUS_CODE, 503),
+            ],
+        );
+
+        assert_eq!(
+            segment_document(&span, Some("order-service")),
+            json!({
+                "name": "order-service",
+                "id": "53995c3f42cd8ad8",
+                "trace_id": "1-5759e988-bd862e3fe1be46a994272793",
+                "start_time": 1_478_293_361.271,
+                "end_time": 1_478_293_361.449,
+                "fault": true,
+                "http": {
+                    "request": { "method": "GET", "url": "https://example.com/orders" },
+                    "response": { "status": 503 },
+                },
+                "metadata": {
+                    "default": {
+                        "http.request.method": "GET",
+                        "url.full": "https://example.com/orders",
+                        "http.response.status_code": 503,
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_subsegment_document() {
+        let mut span = span_data(
+            SpanId::from_hex("defdfd9912dc5a56").unwrap(),
+            SpanKind::Client,
+            vec![KeyValue::new(RPC_SYSTEM, "aws-api")],
+        );
+        span.status = Status::error("failed");
+
+        let document = segment_document(&span, Some("order-service"));
+        assert_eq!(document["name"], "GET /orders");
+        assert_eq!(document["type"], "subsegment");
+        assert_eq!(document["parent_id"], "defdfd9912dc5a56");
+        assert_eq!(document["namespace"], "aws");
+        assert_eq!(document["fault"], true);
+
+        span.attributes = vec![KeyValue::new(HTTP_RESPONSE_STATUS_CODE, 429)];
+        let document = segment_document(&span, None);
+        assert_eq!(document["namespace"], "remote");
+        assert_eq!(document["error"], true);
+        assert_eq!(document["throttle"], true);
+        assert_eq!(document.get("fault"), None);
+    }
+
+    #[test]
+    fn test_parse_daemon_address() {
+        assert_eq!(
+            parse_daemon_address("127.0.0.1:3000"),
+            Some("127.0.0.1:3000".to_string())
+        );
+        assert_eq!(
+            parse_daemon_address("tcp:127.0.0.1:2000 udp:127.0.0.2:2001"),
+            Some("127.0.0.2:2001".to_string())
+        );
+        assert_eq!(parse_daemon_address(" "), None);
+    }
+
+    #[test]
+    fn test_daemon_message_size_limit() {
+        let span = span_data(
+            SpanId::INVALID,
+            SpanKind::Server,
+            vec![KeyValue::new("large", "a".repeat(MAX_MESSAGE_SIZE))],
+        );
+        let message = daemon_message(&span, None).unwrap();
+        assert!(message.len() <= MAX_MESSAGE_SIZE);
+        let document: serde_json::Value =
+            serde_json::from_str(message.strip_prefix(DAEMON_HEADER).unwrap()).unwrap();
+        assert!(document.get("metadata").is_none());
+
+        let span = span_data(
+            SpanId::INVALID,
+            SpanKind::Server,
+            vec![KeyValue::new(URL_FULL, "a".repeat(MAX_MESSAGE_SIZE))],
+        );
+        let err = daemon_message(&span, None).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_export_sends_documents_to_daemon() {
+        let daemon = UdpSocket::bind("127.0.0.1:0").unwrap();
+        daemon
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut exporter = XrayExporter::with_daemon_address(daemon.local_addr().unwrap()).unwrap();
+        exporter.set_resource(&Resource::new(vec![KeyValue::new(
+            SERVICE_NAME,
+            "order-service",
+        )]));
+
+        let result = exporter
+            .export(vec![span_data(SpanId::INVALID, SpanKind::Server, vec![])])
+            .await;
+        assert!(result.is_ok());
+
+        let mut buf = [0u8; 4096];
+        let len = daemon.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        let document = message.strip_prefix(DAEMON_HEADER).unwrap();
+        let document: serde_json::Value = serde_json::from_str(document).unwrap();
+        assert_eq!(document["name"], "order-service");
+    }
+}
@@ -1,41 +1,66 @@
+use base64::engine::{general_purpose::STANDARD, Engine};
 use opentelemetry::logs::AnyValue;
 use opentelemetry::Key;
 use serde_json::{json, Map, Value};
 use std::collections::HashMap;
 
+use crate::logs::exporter::JsonStringPolicy;
+
+/// Converts log values to JSON. Bytes are encoded as base64 strings and map entries are
+/// ordered by key, so equal values always produce the same JSON.
 pub(super) trait IntoJson {
-    fn as_json_value(&self) -> Value;
+    fn as_json_value(&self, string_policy: JsonStringPolicy) -> Value;
 }
 
 impl IntoJson for AnyValue {
-    fn as_json_value(&self) -> Value {
+    fn as_json_value(&self, string_policy: JsonStringPolicy) -> Value {
         match &self {
             AnyValue::Int(value) => json!(value),
             AnyValue::Double(value) => json!(value),
-            AnyValue::String(value) => json!(value.to_string()),
+            AnyValue::String(value) => string_as_json_value(value.as_str(), string_policy),
             AnyValue::Boolean(value) => json!(value),
-            AnyValue::Bytes(_value) => todo!("No support for AnyValue::Bytes yet."),
-            AnyValue::ListAny(value) => value.as_json_value(),
-            AnyValue::Map(value) => value.as_json_value(),
+            AnyValue::Bytes(value) => json!(STANDARD.encode(value.as_slice())),
+            AnyValue::ListAny(value) => value.as_json_value(string_policy),
+            AnyValue::Map(value) => value.as_json_value(string_policy),
             &_ => Value::Null,
         }
     }
 }
 
 impl IntoJson for HashMap<Key, AnyValue> {
-    fn as_json_value(&self) -> Value {
+    fn as_json_value(&self, string_policy: JsonStringPolicy) -> Value {
+        // Sort the entries, as serde_json maps keep the insertion order if its
+        // `preserve_order` feature is enabled.
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
         Value::Object(
-            self.iter()
-                .map(|(k, v)| (k.to_string(), v.as_json_value()))
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.as_json_value(string_policy)))
                 .collect::<Map<String, Value>>(),
         )
     }
 }
 
 impl IntoJson for [AnyValue] {
-    fn as_json_value(&self) -> Value {
-        Value::Array(self.iter().map(IntoJson::as_json_value).collect())
+    fn as_json_value(&self, string_policy: JsonStringPolicy) -> Value {
+        Value::Array(
+            self.iter()
+                .map(|value| value.as_json_value(string_policy))
+                .collect(),
+        )
+    }
+}
+
+fn string_as_json_value(value: &str, string_policy: JsonStringPolicy) -> Value {
+    if string_policy == JsonStringPolicy::EmbedJson
+        && (value.starts_with('{') || value.starts_with('['))
+    {
+        if let Ok(embedded @ (Value::Object(_) | Value::Array(_))) = serde_json::from_str(value) {
+            return embedded;
+        }
     }
+    json!(value)
 }
 
 #[cfg(test)]
@@ -52,10 +77,10 @@ mod tests {
             AnyValue::Int(0),
             AnyValue::Int(-2),
         ];
-        let result = vec.as_json_value();
+        let result = vec.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([1, 2, 3, 0, -2]));
 
-        let result = [].as_json_value();
+        let result = [].as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([]));
 
         let array = [AnyValue::ListAny(Box::new(vec![
@@ -63,18 +88,18 @@ mod tests {
             AnyValue::Int(2),
             AnyValue::Int(3),
         ]))];
-        let result = array.as_json_value();
+        let result = array.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([[1, 2, 3]]));
 
         let array = [
             AnyValue::ListAny(Box::new(vec![AnyValue::Int(1), AnyValue::Int(2)])),
             AnyValue::ListAny(Box::new(vec![AnyValue::Int(3), AnyValue::Int(4)])),
         ];
-        let result = array.as_json_value();
+        let result = array.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([[1, 2], [3, 4]]));
 
         let array = [AnyValue::Boolean(true), AnyValue::Boolean(false)];
-        let result = array.as_json_value();
+        let result = array.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([true, false]));
 
         let array = [
@@ -84,7 +109,7 @@ mod tests {
             AnyValue::Double(0.1),
             AnyValue::Double(-0.5),
         ];
-        let result = array.as_json_value();
+        let result = array.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([1.0, -1.0, 0.0, 0.1, -0.5]));
 
         let array = [
@@ -95,7 +120,7 @@ mod tests {
             AnyValue::String(r#"foo bar"#.into()),
             AnyValue::String(r#""foo bar""#.into()),
         ];
-        let result = array.as_json_value();
+        let result = array.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(
             result,
             json!(["", "a", "\"", "\"\"", "foo bar", "\"foo bar\""])
@@ -103,14 +128,44 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn test_convert_bytes_panics() {
+    fn test_convert_bytes_to_base64() {
         let array = [
             AnyValue::Bytes(Box::new(vec![97u8, 98u8, 99u8])),
+            AnyValue::Bytes(Box::new(vec![0xff, 0x00])),
             AnyValue::Bytes(Box::default()),
         ];
-        let result = array.as_json_value();
-        assert_eq!(result, json!(["abc", ""]));
+        let result = array.as_json_value(JsonStringPolicy::Escape);
+        assert_eq!(result, json!(["YWJj", "/wA=", ""]));
+    }
+
+    #[test]
+    fn test_convert_map_is_ordered_by_key() {
+        let mut map = HashMap::new();
+        for key in ["c", "a", "d", "b"] {
+            map.insert(Key::new(key), AnyValue::String(key.to_string().into()));
+        }
+        assert_eq!(
+            map.as_json_value(JsonStringPolicy::Escape).to_string(),
+            r#"{"a":"a","b":"b","c":"c","d":"d"}"#
+        );
+    }
+
+    #[test]
+    fn test_convert_string_policy() {
+        let array = [
+            AnyValue::String(r#"{"a":[1,"b"]}"#.into()),
+            AnyValue::String("[1, 2]".into()),
+            AnyValue::String("{not json".into()),
+            AnyValue::String("1".into()),
+        ];
+        assert_eq!(
+            array.as_json_value(JsonStringPolicy::Escape),
+            json!([r#"{"a":[1,"b"]}"#, "[1, 2]", "{not json", "1"])
+        );
+        assert_eq!(
+            array.as_json_value(JsonStringPolicy::EmbedJson),
+            json!([{"a": [1, "b"]}, [1, 2], "{not json", "1"])
+        );
     }
 
     #[test]
@@ -121,11 +176,11 @@ mod tests {
         map.insert(Key::new("c"), AnyValue::Int(3));
         map.insert(Key::new("d"), AnyValue::Int(0));
         map.insert(Key::new("e"), AnyValue::Int(-2));
-        let result = map.as_json_value();
+        let result = map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!({"a": 1, "b": 2, "c": 3, "d": 0, "e": -2}));
 
         let map = HashMap::new();
-        let result = map.as_json_value();
+        let result = map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!({}));
 
         let mut inner_map = HashMap::new();
@@ -136,13 +191,13 @@ mod tests {
         map.insert(Key::new("d"), AnyValue::Int(4));
         map.insert(Key::new("e"), AnyValue::Int(5));
         map.insert(Key::new("f"), AnyValue::Map(Box::new(inner_map)));
-        let result = map.as_json_value();
+        let result = map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!({"d":4,"e":5,"f":{"a":1,"b":2,"c":3}}));
 
         let mut map = HashMap::new();
         map.insert(Key::new("True"), AnyValue::Boolean(true));
         map.insert(Key::new("False"), AnyValue::Boolean(false));
-        let result = map.as_json_value();
+        let result = map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!({"True":true,"False":false}));
 
         let mut map = HashMap::new();
@@ -151,7 +206,7 @@ mod tests {
         map.insert(Key::new("c"), AnyValue::Double(0.0));
         map.insert(Key::new("d"), AnyValue::Double(0.1));
         map.insert(Key::new("e"), AnyValue::Double(-0.5));
-        let result = map.as_json_value();
+        let result = map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!({"a":1.0,"b":-1.0,"c":0.0,"d":0.1,"e":-0.5}));
 
         let mut map = HashMap::new();
@@ -164,7 +219,7 @@ mod tests {
         map.insert(Key::new(""), AnyValue::String(r#"empty key"#.into()));
         map.insert(Key::new(r#"""#), AnyValue::String(r#"quote"#.into()));
         map.insert(Key::new(r#""""#), AnyValue::String(r#"quotes"#.into()));
-        let result = map.as_json_value();
+        let result = map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(
             result,
             json!({"a":"","b":"a","c":"\"","d":"\"\"","e":"foo bar","f":"\"foo bar\"","":"empty key","\"":"quote","\"\"":"quotes"})
@@ -194,7 +249,7 @@ mod tests {
             Key::new("d"),
             AnyValue::ListAny(Box::new(empty_vec.clone())),
         );
-        let result = complex_map.as_json_value();
+        let result = complex_map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!({"a":{"a":1,"b":2},"b":{},"c":[1,2],"d":[]}));
 
         let complex_vec = [
@@ -203,12 +258,12 @@ mod tests {
             AnyValue::ListAny(Box::new(simple_vec.clone())),
             AnyValue::ListAny(Box::new(empty_vec.clone())),
         ];
-        let result = complex_vec.as_json_value();
+        let result = complex_vec.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(result, json!([{"a":1,"b":2},{},[1,2],[]]));
 
         let mut nested_complex_map = HashMap::new();
         nested_complex_map.insert(Key::new("a"), AnyValue::Map(Box::new(complex_map.clone())));
-        let result = nested_complex_map.as_json_value();
+        let result = nested_complex_map.as_json_value(JsonStringPolicy::Escape);
         assert_eq!(
             result,
             json!({"a":{"a":{"a":1,"b":2},"b":{},"c":[1,2],"d":[]}})
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tracelogging::win_filetime_from_systemtime;
 use tracelogging_dynamic as tld;
 
@@ -11,6 +11,7 @@ use opentelemetry::{
     logs::{AnyValue, Severity},
     Key,
 };
+use opentelemetry_sdk::Resource;
 use std::{str, time::SystemTime};
 
 use crate::logs::converters::IntoJson;
@@ -20,6 +21,17 @@ pub type ProviderGroup = Option<Cow<'static, str>>;
 
 // thread_local! { static EBW: RefCell<EventBuilder> = RefCell::new(EventBuilder::new());}
 
+/// Encoding of string values nested in map and list attributes, which are exported as JSON.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonStringPolicy {
+    /// Export strings as escaped JSON strings.
+    #[default]
+    Escape,
+    /// Embed strings holding a JSON object or array as raw JSON, so that they can be queried
+    /// without parsing them again. Other strings are escaped.
+    EmbedJson,
+}
+
 /// Exporter config
 #[derive(Debug)]
 pub struct ExporterConfig {
@@ -28,6 +40,19 @@ pub struct ExporterConfig {
     pub keywords_map: HashMap<String, u64>,
     /// default keyword if map is not defined.
     pub default_keyword: u64,
+    /// Emit each log record under its event name (the `event_name` or `name` attribute)
+    /// instead of the exporter's default event name.
+    /// The name is sanitized to contain only ASCII alphanumerics and `_`. Records without a
+    /// usable name fall back to the default event name.
+    pub use_record_event_name: bool,
+    /// Name of an integer attribute to use as the ETW event descriptor Id, e.g. `event_id`.
+    /// Values outside the `u16` range are ignored and the Id stays 0.
+    pub event_id_attribute: Option<String>,
+    /// Name of an integer attribute to use as the ETW event descriptor Version.
+    /// Values outside the `u8` range are ignored and the Version stays 0.
+    pub event_version_attribute: Option<String>,
+    /// Encoding of strings in map and list attributes exported as JSON.
+    pub json_string_policy: JsonStringPolicy,
 }
 
 impl Default for ExporterConfig {
@@ -35,6 +60,10 @@ impl Default for ExporterConfig {
         ExporterConfig {
             keywords_map: HashMap::new(),
             default_keyword: 1,
+            use_record_event_name: false,
+            event_id_attribute: None,
+            event_version_attribute: None,
+            json_string_policy: JsonStringPolicy::default(),
         }
     }
 }
@@ -56,12 +85,58 @@ pub(crate) struct ETWExporter {
     provider: Pin<Arc<tld::Provider>>,
     exporter_config: ExporterConfig,
     event_name: String,
+    cloud_identity: OnceLock<CloudIdentity>,
+}
+
+/// Values of the PartA `ext_cloud_role` and `ext_cloud_roleInstance` fields.
+#[derive(Debug, PartialEq)]
+struct CloudIdentity {
+    role: String,
+    role_instance: Option<String>,
+}
+
+impl CloudIdentity {
+    /// Derives the identity from the `service.name` and `service.instance.id` resource
+    /// attributes.
+    ///
+    /// Without a configured service name, which the SDK defaults to `unknown_service`, the role
+    /// falls back to the executable name. The role instance falls back to the computer name,
+    /// which is the container hostname in Windows containers.
+    fn new(resource: Option<&Resource>, get_env: impl Fn(&str) -> Option<String>) -> Self {
+        let get_attribute = |key: &'static str| {
+            resource
+                .and_then(|resource| resource.get(Key::from_static_str(key)))
+                .map(|value| value.as_str().into_owned())
+                .filter(|value| !value.is_empty())
+        };
+        let role = get_attribute(SERVICE_NAME)
+            .filter(|name| !name.starts_with(UNKNOWN_SERVICE))
+            .or_else(|| {
+                std::env::current_exe().ok().and_then(|path| {
+                    path.file_stem()
+                        .map(|name| name.to_string_lossy().into_owned())
+                })
+            })
+            .unwrap_or_else(|| UNKNOWN_SERVICE.to_string());
+        let role_instance = get_attribute(SERVICE_INSTANCE_ID)
+            .or_else(|| get_env("COMPUTERNAME"))
+            .or_else(|| get_env("HOSTNAME"))
+            .filter(|value| !value.is_empty());
+        CloudIdentity {
+            role,
+            role_instance,
+        }
+    }
 }
 
 const EVENT_ID: &str = "event_id";
 const EVENT_NAME_PRIMARY: &str = "event_name";
 const EVENT_NAME_SECONDARY: &str = "name";
 
+const SERVICE_NAME: &str = "service.name";
+const SERVICE_INSTANCE_ID: &str = "service.instance.id";
+const UNKNOWN_SERVICE: &str = "unknown_service";
+
 // TODO: Implement callback
 fn enabled_callback(
     _source_id: &tld::Guid,
@@ -99,9 +174,25 @@ impl ETWExporter {
             provider,
             exporter_config,
             event_name,
+            cloud_identity: OnceLock::new(),
         }
     }
 
+    /// Sets the resource the PartA cloud role fields are derived from. Only the first
+    /// resource is used.
+    pub(crate) fn set_cloud_identity_resource(&self, resource: &Resource) {
+        let _ = self
+            .cloud_identity
+            .set(CloudIdentity::new(Some(resource), |name| {
+                std::env::var(name).ok()
+            }));
+    }
+
+    fn cloud_identity(&self) -> &CloudIdentity {
+        self.cloud_identity
+            .get_or_init(|| CloudIdentity::new(None, |name| std::env::var(name).ok()))
+    }
+
     // TODO: enable keywords on callback
     // fn register_events(provider: &mut tld::Provider, keyword: u64) {
     //     let levels = [
@@ -133,32 +224,7 @@ impl ETWExporter {
     // }
 
     fn get_severity_level(&self, severity: Severity) -> tld::Level {
-        match severity {
-            Severity::Debug
-            | Severity::Debug2
-            | Severity::Debug3
-            | Severity::Debug4
-            | Severity::Trace
-            | Severity::Trace2
-            | Severity::Trace3
-            | Severity::Trace4 => tld::Level::Verbose,
-
-            Severity::Info | Severity::Info2 | Severity::Info3 | Severity::Info4 => {
-                tld::Level::Informational
-            }
-
-            Severity::Error | Severity::Error2 | Severity::Error3 | Severity::Error4 => {
-                tld::Level::Error
-            }
-
-            Severity::Fatal | Severity::Fatal2 | Severity::Fatal3 | Severity::Fatal4 => {
-                tld::Level::Critical
-            }
-
-            Severity::Warn | Severity::Warn2 | Severity::Warn3 | Severity::Warn4 => {
-                tld::Level::Warning
-            }
-        }
+        get_severity_level(severity)
     }
 
     #[allow(dead_code)]
@@ -171,6 +237,12 @@ impl ETWExporter {
         self.provider.enabled(level.into(), keyword)
     }
 
+    pub(crate) fn enabled_check(&self) -> EnabledCheck {
+        EnabledCheck {
+            provider: self.provider.clone(),
+        }
+    }
+
     pub(crate) fn export_log_data(
         &self,
         log_record: &opentelemetry_sdk::logs::LogRecord,
@@ -195,7 +267,9 @@ impl ETWExporter {
         let mut event = tld::EventBuilder::new();
 
         // reset
-        event.reset(&self.event_name, level, keyword, event_tags);
+        event.reset(&self.get_event_name(log_record), level, keyword, event_tags);
+        let (id, version) = self.get_event_descriptor_id_version(log_record);
+        event.id_version(id, version);
 
         event.add_u16("__csver__", 0x0401u16, tld::OutType::Hex, field_tag);
 
@@ -214,6 +288,56 @@ impl ETWExporter {
         }
     }
 
+    fn get_event_name<'a>(
+        &'a self,
+        log_record: &'a opentelemetry_sdk::logs::LogRecord,
+    ) -> Cow<'a, str> {
+        if !self.exporter_config.use_record_event_name {
+            return Cow::Borrowed(&self.event_name);
+        }
+
+        let mut event_name = None;
+        for (key, value) in log_record.attributes_iter() {
+            match (key.as_str(), value) {
+                (EVENT_NAME_PRIMARY, AnyValue::String(value)) => {
+                    event_name = Some(value.as_str());
+                    break;
+                }
+                (EVENT_NAME_SECONDARY, AnyValue::String(value)) if event_name.is_none() => {
+                    event_name = Some(value.as_str());
+                }
+                _ => {}
+            }
+        }
+
+        match event_name.map(sanitize_event_name) {
+            Some(event_name) if !event_name.is_empty() => event_name,
+            _ => Cow::Borrowed(&self.event_name),
+        }
+    }
+
+    fn get_event_descriptor_id_version(
+        &self,
+        log_record: &opentelemetry_sdk::logs::LogRecord,
+    ) -> (u16, u8) {
+        let get_int = |name: &Option<String>| {
+            let name = name.as_deref()?;
+            log_record
+                .attributes_iter()
+                .find_map(|(key, value)| match value {
+                    AnyValue::Int(value) if key.as_str() == name => Some(*value),
+                    _ => None,
+                })
+        };
+        let id = get_int(&self.exporter_config.event_id_attribute)
+            .and_then(|id| u16::try_from(id).ok())
+            .unwrap_or(0);
+        let version = get_int(&self.exporter_config.event_version_attribute)
+            .and_then(|version| u8::try_from(version).ok())
+            .unwrap_or(0);
+        (id, version)
+    }
+
     fn populate_part_a(
         &self,
         event: &mut tld::EventBuilder,
@@ -225,12 +349,29 @@ impl ETWExporter {
             .or(log_record.observed_timestamp)
             .unwrap_or_else(SystemTime::now);
 
+        let cloud_identity = self.cloud_identity();
+
         const COUNT_TIME: u8 = 1u8;
-        const PART_A_COUNT: u8 = COUNT_TIME;
-        event.add_struct("PartA", PART_A_COUNT, field_tag);
+        const COUNT_ROLE: u8 = 1u8;
+        let part_a_count = COUNT_TIME + COUNT_ROLE + cloud_identity.role_instance.is_some() as u8;
+        event.add_struct("PartA", part_a_count, field_tag);
         {
             let timestamp = win_filetime_from_systemtime!(event_time);
             event.add_filetime("time", timestamp, tld::OutType::Default, field_tag);
+            event.add_str8(
+                "ext_cloud_role",
+                &cloud_identity.role,
+                tld::OutType::Default,
+                field_tag,
+            );
+            if let Some(role_instance) = &cloud_identity.role_instance {
+                event.add_str8(
+                    "ext_cloud_roleInstance",
+                    role_instance,
+                    tld::OutType::Default,
+                    field_tag,
+                );
+            }
         }
     }
 
@@ -260,7 +401,12 @@ impl ETWExporter {
         event.add_str8("_typeName", "Logs", tld::OutType::Default, 0);
 
         if let Some(body) = log_record.body.clone() {
-            add_attribute_to_event(event, &Key::new("body"), &body);
+            add_attribute_to_event(
+                event,
+                &Key::new("body"),
+                &body,
+                self.exporter_config.json_string_policy,
+            );
         }
 
         event.add_u8("severityNumber", level.as_int(), tld::OutType::Default, 0);
@@ -322,7 +468,12 @@ impl ETWExporter {
                         continue;
                     }
                     _ => {
-                        add_attribute_to_event(event, key, value);
+                        add_attribute_to_event(
+                            event,
+                            key,
+                            value,
+                            self.exporter_config.json_string_policy,
+                        );
                     }
                 }
             }
@@ -366,9 +517,83 @@ impl opentelemetry_sdk::export::logs::LogExporter for ETWExporter {
         self.provider
             .enabled(self.get_severity_level(level), keyword)
     }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.set_cloud_identity_resource(resource);
+    }
+}
+
+/// Cheap check whether any ETW session listens to the provider at a level and keyword.
+///
+/// Appenders can use it to skip building log records nobody consumes. Unlike
+/// `Logger::event_enabled`, it neither resolves the keyword from the logger name nor needs
+/// a log record. The check can be cloned and used after the processor was moved into the
+/// logger provider.
+#[derive(Clone)]
+pub struct EnabledCheck {
+    provider: Pin<Arc<tld::Provider>>,
+}
+
+impl EnabledCheck {
+    /// Returns whether an ETW session has the provider enabled for `level` and `keyword`.
+    pub fn enabled(&self, level: Severity, keyword: u64) -> bool {
+        self.provider.enabled(get_severity_level(level), keyword)
+    }
+}
+
+impl Debug for EnabledCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ETW enabled check")
+    }
 }
 
-fn add_attribute_to_event(event: &mut tld::EventBuilder, key: &Key, value: &AnyValue) {
+fn get_severity_level(severity: Severity) -> tld::Level {
+    match severity {
+        Severity::Debug
+        | Severity::Debug2
+        | Severity::Debug3
+        | Severity::Debug4
+        | Severity::Trace
+        | Severity::Trace2
+        | Severity::Trace3
+        | Severity::Trace4 => tld::Level::Verbose,
+
+        Severity::Info | Severity::Info2 | Severity::Info3 | Severity::Info4 => {
+            tld::Level::Informational
+        }
+
+        Severity::Error | Severity::Error2 | Severity::Error3 | Severity::Error4 => {
+            tld::Level::Error
+        }
+
+        Severity::Fatal | Severity::Fatal2 | Severity::Fatal3 | Severity::Fatal4 => {
+            tld::Level::Critical
+        }
+
+        Severity::Warn | Severity::Warn2 | Severity::Warn3 | Severity::Warn4 => tld::Level::Warning,
+    }
+}
+
+/// Replaces every character which is not an ASCII alphanumeric or `_` with `_`.
+fn sanitize_event_name(name: &str) -> Cow<'_, str> {
+    let is_valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if name.chars().all(is_valid) {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(
+            name.chars()
+                .map(|c| if is_valid(c) { c } else { '_' })
+                .collect(),
+        )
+    }
+}
+
+fn add_attribute_to_event(
+    event: &mut tld::EventBuilder,
+    key: &Key,
+    value: &AnyValue,
+    json_string_policy: JsonStringPolicy,
+) {
     match value {
         AnyValue::Boolean(b) => {
             event.add_bool32(key.as_str(), *b as i32, tld::OutType::Default, 0);
@@ -388,7 +613,7 @@ fn add_attribute_to_event(event: &mut tld::EventBuilder, key: &Key, value: &AnyV
         AnyValue::ListAny(l) => {
             event.add_str8(
                 key.as_str(),
-                l.as_json_value().to_string(),
+                l.as_json_value(json_string_policy).to_string(),
                 tld::OutType::Json,
                 0,
             );
@@ -396,7 +621,7 @@ fn add_attribute_to_event(event: &mut tld::EventBuilder, key: &Key, value: &AnyV
         AnyValue::Map(m) => {
             event.add_str8(
                 key.as_str(),
-                m.as_json_value().to_string(),
+                m.as_json_value(json_string_policy).to_string(),
                 tld::OutType::Json,
                 0,
             );
@@ -408,6 +633,7 @@ fn add_attribute_to_event(event: &mut tld::EventBuilder, key: &Key, value: &AnyV
 #[cfg(test)]
 mod tests {
     use super::*;
+    use opentelemetry::logs::LogRecord as _;
     use opentelemetry::logs::Severity;
 
     #[test]
@@ -425,6 +651,140 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_get_event_name() {
+        let exporter = ETWExporter::new(
+            "test-provider-name",
+            "test-event-name".to_string(),
+            None,
+            ExporterConfig {
+                use_record_event_name: true,
+                ..Default::default()
+            },
+        );
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        assert_eq!(exporter.get_event_name(&record), "test-event-name");
+
+        record.add_attribute("name", "secondary");
+        assert_eq!(exporter.get_event_name(&record), "secondary");
+
+        record.add_attribute("event_name", "my-event.name");
+        assert_eq!(exporter.get_event_name(&record), "my_event_name");
+
+        let exporter = ETWExporter::new(
+            "test-provider-name",
+            "test-event-name".to_string(),
+            None,
+            ExporterConfig::default(),
+        );
+        assert_eq!(exporter.get_event_name(&record), "test-event-name");
+    }
+
+    #[test]
+    fn test_get_event_descriptor_id_version() {
+        let exporter = ETWExporter::new(
+            "test-provider-name",
+            "test-event-name".to_string(),
+            None,
+            ExporterConfig {
+                event_id_attribute: Some("event_id".into()),
+                event_version_attribute: Some("event.version".into()),
+                ..Default::default()
+            },
+        );
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        assert_eq!(exporter.get_event_descriptor_id_version(&record), (0, 0));
+
+        record.add_attribute("event_id", 20);
+        record.add_attribute("event.version", 3);
+        assert_eq!(exporter.get_event_descriptor_id_version(&record), (20, 3));
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.add_attribute("event_id", 70_000);
+        record.add_attribute("event.version", "3");
+        assert_eq!(exporter.get_event_descriptor_id_version(&record), (0, 0));
+
+        let exporter = ETWExporter::new(
+            "test-provider-name",
+            "test-event-name".to_string(),
+            None,
+            ExporterConfig::default(),
+        );
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.add_attribute("event_id", 20);
+        assert_eq!(exporter.get_event_descriptor_id_version(&record), (0, 0));
+    }
+
+    #[test]
+    fn test_cloud_identity() {
+        let no_env = |_: &str| None;
+        let resource = Resource::new(vec![
+            opentelemetry::KeyValue::new(SERVICE_NAME, "checkout"),
+            opentelemetry::KeyValue::new(SERVICE_INSTANCE_ID, "instance-1"),
+        ]);
+        assert_eq!(
+            CloudIdentity::new(Some(&resource), no_env),
+            CloudIdentity {
+                role: "checkout".into(),
+                role_instance: Some("instance-1".into()),
+            }
+        );
+
+        let exe_name = std::env::current_exe()
+            .unwrap()
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let resource = Resource::new(vec![opentelemetry::KeyValue::new(
+            SERVICE_NAME,
+            "unknown_service:test",
+        )]);
+        assert_eq!(
+            CloudIdentity::new(Some(&resource), |name| (name == "COMPUTERNAME")
+                .then(|| "container-host".to_string())),
+            CloudIdentity {
+                role: exe_name.clone(),
+                role_instance: Some("container-host".into()),
+            }
+        );
+        assert_eq!(
+            CloudIdentity::new(None, |name| (name == "HOSTNAME")
+                .then(|| "linux-host".to_string())),
+            CloudIdentity {
+                role: exe_name.clone(),
+                role_instance: Some("linux-host".into()),
+            }
+        );
+        assert_eq!(
+            CloudIdentity::new(None, |_| Some(String::new())),
+            CloudIdentity {
+                role: exe_name.clone(),
+                role_instance: None,
+            }
+        );
+        assert_eq!(
+            CloudIdentity::new(None, no_env),
+            CloudIdentity {
+                role: exe_name,
+                role_instance: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_sanitize_event_name() {
+        assert!(matches!(
+            sanitize_event_name("Valid_Name1"),
+            Cow::Borrowed("Valid_Name1")
+        ));
+        assert_eq!(sanitize_event_name("a b/c-d"), "a_b_c_d");
+        assert_eq!(sanitize_event_name("événement"), "_v_nement");
+        assert_eq!(sanitize_event_name(""), "");
+    }
+
     #[test]
     fn test_get_severity_level() {
         let exporter = ETWExporter::new(
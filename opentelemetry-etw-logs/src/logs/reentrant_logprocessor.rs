@@ -30,6 +30,22 @@ impl ReentrantLogProcessor {
             event_exporter: exporter,
         }
     }
+
+    /// Returns whether an ETW session has the provider enabled for `level` and `keyword`.
+    ///
+    /// This is cheaper than [`Logger::event_enabled`] and meant to be called by appenders
+    /// before building a log record. See [`EnabledCheck`].
+    ///
+    /// [`Logger::event_enabled`]: opentelemetry::logs::Logger::event_enabled
+    pub fn enabled(&self, level: opentelemetry::logs::Severity, keyword: u64) -> bool {
+        self.enabled_check().enabled(level, keyword)
+    }
+
+    /// Returns a cloneable [`EnabledCheck`] which stays usable after the processor was added
+    /// to a logger provider.
+    pub fn enabled_check(&self) -> EnabledCheck {
+        self.event_exporter.enabled_check()
+    }
 }
 
 impl opentelemetry_sdk::logs::LogProcessor for ReentrantLogProcessor {
@@ -49,6 +65,10 @@ impl opentelemetry_sdk::logs::LogProcessor for ReentrantLogProcessor {
         Ok(())
     }
 
+    fn set_resource(&self, resource: &opentelemetry_sdk::Resource) {
+        self.event_exporter.set_cloud_identity_resource(resource);
+    }
+
     #[cfg(feature = "logs_level_enabled")]
     fn event_enabled(
         &self,
@@ -89,6 +109,37 @@ mod tests {
         assert!(processor.force_flush().is_ok());
     }
 
+    #[test]
+    fn test_enabled() {
+        use opentelemetry::logs::Severity;
+
+        let processor = ReentrantLogProcessor::new(
+            "test-provider-name",
+            "test-event-name".into(),
+            None,
+            ExporterConfig::default(),
+        );
+        let check = processor.enabled_check();
+        assert!(!processor.enabled(Severity::Info, 1));
+        let _provider = opentelemetry_sdk::logs::LoggerProvider::builder()
+            .with_log_processor(processor)
+            .build();
+
+        // No ETW session listens to the provider in tests, so every level and keyword is
+        // disabled, including for the check kept after the processor was moved.
+        for level in [
+            Severity::Trace,
+            Severity::Debug,
+            Severity::Info,
+            Severity::Error,
+        ] {
+            for keyword in [0, 1, u64::MAX] {
+                assert!(!check.enabled(level, keyword));
+                assert!(!check.clone().enabled(level, keyword));
+            }
+        }
+    }
+
     #[test]
     fn test_emit() {
         let processor = ReentrantLogProcessor::new(
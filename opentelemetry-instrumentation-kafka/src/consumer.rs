@@ -0,0 +1,416 @@
+use crate::propagation::HeaderExtractor;
+use crate::{instrumentation_scope, MESSAGING_SYSTEM_KAFKA};
+use opentelemetry::global::{self, BoxedTracer};
+use opentelemetry::metrics::{Counter, Gauge, Meter};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{
+    FutureExt, Span, SpanKind, Status, TraceContextExt, Tracer, TracerProvider as _,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_semantic_conventions::{attribute, metric};
+use rdkafka::client::ClientContext;
+use rdkafka::consumer::ConsumerContext;
+use rdkafka::message::Message;
+use rdkafka::statistics::Statistics;
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::sync::Arc;
+
+const OPERATION_PROCESS: &str = "process";
+
+/// Name of the consumer lag gauge reported from librdkafka statistics.
+const CONSUMER_LAG: &str = "messaging.kafka.consumer.lag";
+
+/// Instrumentation for messages received by a Kafka consumer.
+///
+/// The consumer itself is not wrapped, as messages are usually processed independently of
+/// how they are received (stream, poll loop, ...). Instead, [`process_context`] and
+/// [`in_process_span`] create a `process` span of kind [`SpanKind::Consumer`] for a
+/// received message. The span is parented to the context extracted from the message
+/// headers, if any. The global propagator is used unless one is set with
+/// [`with_propagator`](ConsumerInstrumentation::with_propagator).
+///
+/// [`process_context`]: ConsumerInstrumentation::process_context
+/// [`in_process_span`]: ConsumerInstrumentation::in_process_span
+pub struct ConsumerInstrumentation {
+    tracer: BoxedTracer,
+    propagator: Option<Box<dyn TextMapPropagator + Send + Sync>>,
+    consumed_messages: Counter<u64>,
+    consumer_lag: Gauge<i64>,
+    consumer_group: Option<String>,
+}
+
+impl ConsumerInstrumentation {
+    /// Create a new instrumentation using the global tracer and meter providers.
+    pub fn new() -> Self {
+        let tracer = global::tracer_provider().tracer_with_scope(instrumentation_scope());
+        let meter = global::meter_with_scope(instrumentation_scope());
+        Self::with_tracer_and_meter(tracer, &meter)
+    }
+
+    /// Create a new instrumentation recording telemetry with the given tracer and meter.
+    pub fn with_tracer_and_meter<T, S>(tracer: T, meter: &Meter) -> Self
+    where
+        T: Tracer<Span = S> + Send + Sync + 'static,
+        S: Span + Send + Sync + 'static,
+    {
+        ConsumerInstrumentation {
+            tracer: BoxedTracer::new(Box::new(tracer)),
+            propagator: None,
+            consumed_messages: meter
+                .u64_counter(metric::MESSAGING_CLIENT_CONSUMED_MESSAGES)
+                .with_description("Number of messages that were delivered to the application.")
+                .with_unit("{message}")
+                .build(),
+            consumer_lag: meter
+                .i64_gauge(CONSUMER_LAG)
+                .with_description(
+                    "Number of messages the consumer group is behind the partition high watermark.",
+                )
+                .with_unit("{message}")
+                .build(),
+            consumer_group: None,
+        }
+    }
+
+    /// Extract the propagated context with the given propagator instead of the global one.
+    pub fn with_propagator<P>(mut self, propagator: P) -> Self
+    where
+        P: TextMapPropagator + Send + Sync + 'static,
+    {
+        self.propagator = Some(Box::new(propagator));
+        self
+    }
+
+    /// Set the consumer group name recorded as `messaging.consumer.group.name`.
+    pub fn with_consumer_group(mut self, consumer_group: impl Into<String>) -> Self {
+        self.consumer_group = Some(consumer_group.into());
+        self
+    }
+
+    /// Starts a `process` span for the given message and returns a context containing it.
+    ///
+    /// The span must be ended by the caller, e.g. with `cx.span().end()`. Also records the
+    /// `messaging.client.consumed.messages` metric.
+    pub fn process_context<M: Message>(&self, message: &M) -> Context {
+        // Messages without propagated context start a new trace, independent of the
+        // current context of the consuming thread.
+        let parent_cx = match message.headers() {
+            Some(headers) => {
+                let extract = |propagator: &dyn TextMapPropagator| {
+                    propagator.extract_with_context(&Context::new(), &HeaderExtractor(headers))
+                };
+                match &self.propagator {
+                    Some(propagator) => extract(propagator.as_ref()),
+                    None => global::get_text_map_propagator(extract),
+                }
+            }
+            None => Context::new(),
+        };
+
+        let partition = message.partition().to_string();
+        let mut attributes = vec![
+            KeyValue::new(attribute::MESSAGING_SYSTEM, MESSAGING_SYSTEM_KAFKA),
+            KeyValue::new(attribute::MESSAGING_OPERATION_NAME, OPERATION_PROCESS),
+            KeyValue::new(attribute::MESSAGING_OPERATION_TYPE, OPERATION_PROCESS),
+            KeyValue::new(
+                attribute::MESSAGING_DESTINATION_NAME,
+                message.topic().to_owned(),
+            ),
+            KeyValue::new(
+                attribute::MESSAGING_DESTINATION_PARTITION_ID,
+                partition.clone(),
+            ),
+            KeyValue::new(attribute::MESSAGING_KAFKA_OFFSET, message.offset()),
+        ];
+        if let Some(consumer_group) = &self.consumer_group {
+            attributes.push(KeyValue::new(
+                attribute::MESSAGING_CONSUMER_GROUP_NAME,
+                consumer_group.clone(),
+            ));
+        }
+        if let Some(key) = message.key().and_then(|key| std::str::from_utf8(key).ok()) {
+            attributes.push(KeyValue::new(
+                attribute::MESSAGING_KAFKA_MESSAGE_KEY,
+                key.to_owned(),
+            ));
+        }
+        match message.payload() {
+            Some(payload) => attributes.push(KeyValue::new(
+                attribute::MESSAGING_MESSAGE_BODY_SIZE,
+                payload.len() as i64,
+            )),
+            None => attributes.push(KeyValue::new(
+                attribute::MESSAGING_KAFKA_MESSAGE_TOMBSTONE,
+                true,
+            )),
+        }
+
+        let span = self
+            .tracer
+            .span_builder(format!("{} {}", OPERATION_PROCESS, message.topic()))
+            .with_kind(SpanKind::Consumer)
+            .with_attributes(attributes)
+            .start_with_context(&self.tracer, &parent_cx);
+
+        let mut metric_attributes = vec![
+            KeyValue::new(attribute::MESSAGING_SYSTEM, MESSAGING_SYSTEM_KAFKA),
+            KeyValue::new(attribute::MESSAGING_OPERATION_NAME, OPERATION_PROCESS),
+            KeyValue::new(
+                attribute::MESSAGING_DESTINATION_NAME,
+                message.topic().to_owned(),
+            ),
+            KeyValue::new(attribute::MESSAGING_DESTINATION_PARTITION_ID, partition),
+        ];
+        if let Some(consumer_group) = &self.consumer_group {
+            metric_attributes.push(KeyValue::new(
+                attribute::MESSAGING_CONSUMER_GROUP_NAME,
+                consumer_group.clone(),
+            ));
+        }
+        self.consumed_messages.add(1, &metric_attributes);
+
+        parent_cx.with_span(span)
+    }
+
+    /// Runs `future` within a `process` span for the given message.
+    ///
+    /// The span status is set to error if the future resolves to an error.
+    pub async fn in_process_span<M, F, T, E>(&self, message: &M, future: F) -> Result<T, E>
+    where
+        M: Message,
+        F: Future<Output = Result<T, E>>,
+        E: Display,
+    {
+        let cx = self.process_context(message);
+        let result = future.with_context(cx.clone()).await;
+        let span = cx.span();
+        if let Err(err) = &result {
+            span.set_status(Status::error(err.to_string()));
+        }
+        span.end();
+        result
+    }
+
+    /// Records the consumer lag of every assigned partition from librdkafka statistics.
+    ///
+    /// Statistics are only emitted when `statistics.interval.ms` is configured on the consumer.
+    pub fn record_statistics(&self, statistics: &Statistics) {
+        for (topic_name, topic) in &statistics.topics {
+            for partition in topic.partitions.values() {
+                // Partition -1 is librdkafka's internal unassigned partition and a lag of
+                // -1 means it is not known yet.
+                if partition.partition < 0 || partition.consumer_lag < 0 {
+                    continue;
+                }
+                let mut attributes = vec![
+                    KeyValue::new(attribute::MESSAGING_SYSTEM, MESSAGING_SYSTEM_KAFKA),
+                    KeyValue::new(attribute::MESSAGING_DESTINATION_NAME, topic_name.clone()),
+                    KeyValue::new(
+                        attribute::MESSAGING_DESTINATION_PARTITION_ID,
+                        partition.partition.to_string(),
+                    ),
+                ];
+                if let Some(consumer_group) = &self.consumer_group {
+                    attributes.push(KeyValue::new(
+                        attribute::MESSAGING_CONSUMER_GROUP_NAME,
+                        consumer_group.clone(),
+                    ));
+                }
+                self.consumer_lag
+                    .record(partition.consumer_lag, &attributes);
+            }
+        }
+    }
+}
+
+impl Default for ConsumerInstrumentation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ConsumerInstrumentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsumerInstrumentation")
+            .field("consumer_group", &self.consumer_group)
+            .finish()
+    }
+}
+
+/// A consumer context reporting consumer lag from librdkafka statistics.
+///
+/// Create the consumer with [`ClientConfig::create_with_context`] and set
+/// `statistics.interval.ms` to enable the statistics callback.
+///
+/// [`ClientConfig::create_with_context`]: rdkafka::ClientConfig::create_with_context
+#[derive(Clone, Debug)]
+pub struct InstrumentedConsumerContext {
+    instrumentation: Arc<ConsumerInstrumentation>,
+}
+
+impl InstrumentedConsumerContext {
+    /// Create a context recording statistics with the given instrumentation.
+    pub fn new(instrumentation: Arc<ConsumerInstrumentation>) -> Self {
+        InstrumentedConsumerContext { instrumentation }
+    }
+
+    /// Returns the instrumentation, to create `process` spans for received messages.
+    pub fn instrumentation(&self) -> &Arc<ConsumerInstrumentation> {
+        &self.instrumentation
+    }
+}
+
+impl ClientContext for InstrumentedConsumerContext {
+    fn stats(&self, statistics: Statistics) {
+        self.instrumentation.record_statistics(&statistics);
+    }
+}
+
+impl ConsumerContext for InstrumentedConsumerContext {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry::trace::{SpanId, TraceId};
+    use opentelemetry::Value;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use rdkafka::message::{Header, OwnedHeaders, OwnedMessage, Timestamp};
+
+    fn instrumentation(
+        exporter: &InMemorySpanExporter,
+    ) -> (TracerProvider, ConsumerInstrumentation) {
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let meter = SdkMeterProvider::default().meter("test");
+        let instrumentation =
+            ConsumerInstrumentation::with_tracer_and_meter(provider.tracer("test"), &meter)
+                .with_propagator(TraceContextPropagator::new())
+                .with_consumer_group("group");
+        (provider, instrumentation)
+    }
+
+    fn message(headers: Option<OwnedHeaders>) -> OwnedMessage {
+        OwnedMessage::new(
+            Some(b"payload".to_vec()),
+            Some(b"key".to_vec()),
+            "orders".to_string(),
+            Timestamp::NotAvailable,
+            3,
+            42,
+            headers,
+        )
+    }
+
+    #[test]
+    fn process_span_is_child_of_propagated_context() {
+        let exporter = InMemorySpanExporter::default();
+        let (_provider, instrumentation) = instrumentation(&exporter);
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "traceparent",
+            value: Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        });
+
+        instrumentation
+            .process_context(&message(Some(headers)))
+            .span()
+            .end();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.name, "process orders");
+        assert_eq!(span.span_kind, SpanKind::Consumer);
+        assert_eq!(
+            span.span_context.trace_id(),
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap()
+        );
+        assert_eq!(
+            span.parent_span_id,
+            SpanId::from_hex("00f067aa0ba902b7").unwrap()
+        );
+
+        let attribute = |key: &str| {
+            span.attributes
+                .iter()
+                .find(|kv| kv.key.as_str() == key)
+                .map(|kv| kv.value.clone())
+        };
+        assert_eq!(
+            attribute(attribute::MESSAGING_SYSTEM),
+            Some(Value::from("kafka"))
+        );
+        assert_eq!(
+            attribute(attribute::MESSAGING_DESTINATION_PARTITION_ID),
+            Some(Value::from("3"))
+        );
+        assert_eq!(
+            attribute(attribute::MESSAGING_KAFKA_OFFSET),
+            Some(Value::from(42))
+        );
+        assert_eq!(
+            attribute(attribute::MESSAGING_KAFKA_MESSAGE_KEY),
+            Some(Value::from("key"))
+        );
+        assert_eq!(
+            attribute(attribute::MESSAGING_CONSUMER_GROUP_NAME),
+            Some(Value::from("group"))
+        );
+    }
+
+    #[test]
+    fn process_span_ignores_current_context() {
+        let exporter = InMemorySpanExporter::default();
+        let (_provider, instrumentation) = instrumentation(&exporter);
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "content-type",
+            value: Some("application/json"),
+        });
+
+        let current = opentelemetry::trace::SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            opentelemetry::trace::TraceFlags::SAMPLED,
+            false,
+            Default::default(),
+        );
+        let _guard = Context::new().with_remote_span_context(current).attach();
+        for headers in [Some(headers), None] {
+            instrumentation
+                .process_context(&message(headers))
+                .span()
+                .end();
+        }
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 2);
+        for span in spans {
+            assert_eq!(span.parent_span_id, SpanId::INVALID);
+        }
+    }
+
+    #[tokio::test]
+    async fn in_process_span_records_error_status() {
+        let exporter = InMemorySpanExporter::default();
+        let (_provider, instrumentation) = instrumentation(&exporter);
+
+        let result = instrumentation
+            .in_process_span(&message(None), async {
+                assert!(Context::current().span().span_context().is_valid());
+                Err::<(), _>("handler failed")
+            })
+            .await;
+        assert!(result.is_err());
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        // Messages without headers start a new trace.
+        assert_eq!(spans[0].parent_span_id, SpanId::INVALID);
+        assert_eq!(spans[0].status, Status::error("handler failed"));
+    }
+}
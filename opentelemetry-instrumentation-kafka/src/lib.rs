@@ -0,0 +1,89 @@
+//! OpenTelemetry instrumentation for Kafka clients built on [`rdkafka`].
+//!
+//! This crate follows the [messaging semantic conventions] for Kafka:
+//!
+//! - [`InstrumentedFutureProducer`] wraps a [`FutureProducer`], creating a `publish`
+//!   span for every sent message, injecting the span context into the message headers
+//!   and recording the `messaging.client.sent.messages` metric.
+//! - [`ConsumerInstrumentation`] creates `process` spans for received messages,
+//!   parented to the context propagated through the message headers, and records the
+//!   `messaging.client.consumed.messages` metric. Consumer lag is reported from
+//!   librdkafka statistics, either manually via [`ConsumerInstrumentation::record_statistics`]
+//!   or by creating the consumer with an [`InstrumentedConsumerContext`].
+//! - [`HeaderInjector`] and [`HeaderExtractor`] propagate context through Kafka headers
+//!   using any [`TextMapPropagator`]. The wrappers use the globally configured propagator
+//!   unless one is set with their `with_propagator` methods.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use opentelemetry_instrumentation_kafka::{ConsumerInstrumentation, InstrumentedFutureProducer};
+//! use rdkafka::consumer::{Consumer, StreamConsumer};
+//! use rdkafka::producer::{FutureProducer, FutureRecord};
+//! use rdkafka::ClientConfig;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let producer: FutureProducer = ClientConfig::new()
+//!     .set("bootstrap.servers", "localhost:9092")
+//!     .create()?;
+//! let producer = InstrumentedFutureProducer::new(producer);
+//! producer
+//!     .send(
+//!         FutureRecord::to("orders").key("order-1").payload("created"),
+//!         Duration::from_secs(1),
+//!     )
+//!     .await
+//!     .map_err(|(err, _)| err)?;
+//!
+//! let consumer: StreamConsumer = ClientConfig::new()
+//!     .set("bootstrap.servers", "localhost:9092")
+//!     .set("group.id", "order-processor")
+//!     .create()?;
+//! consumer.subscribe(&["orders"])?;
+//! let instrumentation = ConsumerInstrumentation::new().with_consumer_group("order-processor");
+//! let message = consumer.recv().await?;
+//! instrumentation
+//!     .in_process_span(&message, async {
+//!         // handle the message
+//!         Ok::<_, std::io::Error>(())
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [messaging semantic conventions]: https://github.com/open-telemetry/semantic-conventions/blob/main/docs/messaging/kafka.md
+//! [`FutureProducer`]: rdkafka::producer::FutureProducer
+//! [`TextMapPropagator`]: opentelemetry::propagation::TextMapPropagator
+#![warn(missing_debug_implementations, missing_docs)]
+
+mod consumer;
+mod producer;
+mod propagation;
+
+pub use consumer::{ConsumerInstrumentation, InstrumentedConsumerContext};
+pub use producer::InstrumentedFutureProducer;
+pub use propagation::{HeaderExtractor, HeaderInjector};
+
+use opentelemetry::InstrumentationScope;
+use opentelemetry_semantic_conventions::SCHEMA_URL;
+use rdkafka::error::KafkaError;
+
+/// Value of the `messaging.system` attribute for Kafka.
+const MESSAGING_SYSTEM_KAFKA: &str = "kafka";
+
+fn instrumentation_scope() -> InstrumentationScope {
+    InstrumentationScope::builder(env!("CARGO_PKG_NAME"))
+        .with_version(env!("CARGO_PKG_VERSION"))
+        .with_schema_url(SCHEMA_URL)
+        .build()
+}
+
+/// Maps a Kafka error to the low-cardinality `error.type` attribute value.
+fn error_type(err: &KafkaError) -> String {
+    match err.rdkafka_error_code() {
+        Some(code) => format!("{:?}", code),
+        None => "_OTHER".to_string(),
+    }
+}
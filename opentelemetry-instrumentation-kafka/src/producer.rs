@@ -0,0 +1,261 @@
+use crate::propagation::HeaderInjector;
+use crate::{error_type, instrumentation_scope, MESSAGING_SYSTEM_KAFKA};
+use opentelemetry::global::{self, BoxedTracer};
+use opentelemetry::metrics::{Counter, Meter};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::{Span, SpanKind, Status, TraceContextExt, Tracer, TracerProvider as _};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_semantic_conventions::{attribute, metric};
+use rdkafka::client::{ClientContext, DefaultClientContext};
+use rdkafka::message::ToBytes;
+use rdkafka::producer::future_producer::OwnedDeliveryResult;
+use rdkafka::producer::FutureProducer;
+use rdkafka::producer::FutureRecord;
+use rdkafka::util::{AsyncRuntime, DefaultRuntime, Timeout};
+use std::fmt;
+
+const OPERATION_PUBLISH: &str = "publish";
+
+/// A [`FutureProducer`] which traces every sent message.
+///
+/// Each call to [`send`](InstrumentedFutureProducer::send) creates a `publish` span of kind
+/// [`SpanKind::Producer`] as a child of the current context, injects it into the record
+/// headers and records the `messaging.client.sent.messages` metric once the delivery outcome
+/// is known. The global propagator is used unless one is set with
+/// [`with_propagator`](InstrumentedFutureProducer::with_propagator).
+pub struct InstrumentedFutureProducer<C = DefaultClientContext, R = DefaultRuntime>
+where
+    C: ClientContext + 'static,
+{
+    inner: FutureProducer<C, R>,
+    tracer: BoxedTracer,
+    propagator: Option<Box<dyn TextMapPropagator + Send + Sync>>,
+    sent_messages: Counter<u64>,
+}
+
+impl<C, R> InstrumentedFutureProducer<C, R>
+where
+    C: ClientContext + 'static,
+    R: AsyncRuntime,
+{
+    /// Wraps the given producer, using the global tracer and meter providers.
+    pub fn new(producer: FutureProducer<C, R>) -> Self {
+        let tracer = global::tracer_provider().tracer_with_scope(instrumentation_scope());
+        let meter = global::meter_with_scope(instrumentation_scope());
+        Self::with_tracer_and_meter(producer, tracer, &meter)
+    }
+
+    /// Wraps the given producer, recording telemetry with the given tracer and meter.
+    pub fn with_tracer_and_meter<T, S>(
+        producer: FutureProducer<C, R>,
+        tracer: T,
+        meter: &Meter,
+    ) -> Self
+    where
+        T: Tracer<Span = S> + Send + Sync + 'static,
+        S: Span + Send + Sync + 'static,
+    {
+        InstrumentedFutureProducer {
+            inner: producer,
+            tracer: BoxedTracer::new(Box::new(tracer)),
+            propagator: None,
+            sent_messages: meter
+                .u64_counter(metric::MESSAGING_CLIENT_SENT_MESSAGES)
+                .with_description("Number of messages producer attempted to send to the broker.")
+                .with_unit("{message}")
+                .build(),
+        }
+    }
+
+    /// Inject the span context with the given propagator instead of the global one.
+    pub fn with_propagator<P>(mut self, propagator: P) -> Self
+    where
+        P: TextMapPropagator + Send + Sync + 'static,
+    {
+        self.propagator = Some(Box::new(propagator));
+        self
+    }
+
+    /// Returns the wrapped producer.
+    pub fn inner(&self) -> &FutureProducer<C, R> {
+        &self.inner
+    }
+
+    /// Sends a message to Kafka, see [`FutureProducer::send`].
+    ///
+    /// Any headers already present on the record are preserved.
+    pub async fn send<K, P, T>(
+        &self,
+        mut record: FutureRecord<'_, K, P>,
+        queue_timeout: T,
+    ) -> OwnedDeliveryResult
+    where
+        K: ToBytes + ?Sized,
+        P: ToBytes + ?Sized,
+        T: Into<Timeout>,
+    {
+        let mut attributes = vec![
+            KeyValue::new(attribute::MESSAGING_SYSTEM, MESSAGING_SYSTEM_KAFKA),
+            KeyValue::new(attribute::MESSAGING_OPERATION_NAME, OPERATION_PUBLISH),
+            KeyValue::new(attribute::MESSAGING_OPERATION_TYPE, OPERATION_PUBLISH),
+            KeyValue::new(
+                attribute::MESSAGING_DESTINATION_NAME,
+                record.topic.to_owned(),
+            ),
+        ];
+        if let Some(key) = record
+            .key
+            .and_then(|key| std::str::from_utf8(key.to_bytes()).ok())
+        {
+            attributes.push(KeyValue::new(
+                attribute::MESSAGING_KAFKA_MESSAGE_KEY,
+                key.to_owned(),
+            ));
+        }
+        match record.payload {
+            Some(payload) => attributes.push(KeyValue::new(
+                attribute::MESSAGING_MESSAGE_BODY_SIZE,
+                payload.to_bytes().len() as i64,
+            )),
+            None => attributes.push(KeyValue::new(
+                attribute::MESSAGING_KAFKA_MESSAGE_TOMBSTONE,
+                true,
+            )),
+        }
+
+        let span = self
+            .tracer
+            .span_builder(format!("{} {}", OPERATION_PUBLISH, record.topic))
+            .with_kind(SpanKind::Producer)
+            .with_attributes(attributes)
+            .start(&self.tracer);
+        let cx = Context::current_with_span(span);
+
+        let mut injector = HeaderInjector::new(record.headers.take().unwrap_or_default());
+        match &self.propagator {
+            Some(propagator) => propagator.inject_context(&cx, &mut injector),
+            None => global::get_text_map_propagator(|propagator| {
+                propagator.inject_context(&cx, &mut injector)
+            }),
+        }
+        let mut metric_attributes = vec![
+            KeyValue::new(attribute::MESSAGING_SYSTEM, MESSAGING_SYSTEM_KAFKA),
+            KeyValue::new(attribute::MESSAGING_OPERATION_NAME, OPERATION_PUBLISH),
+            KeyValue::new(
+                attribute::MESSAGING_DESTINATION_NAME,
+                record.topic.to_owned(),
+            ),
+        ];
+        let record = record.headers(injector.into_headers());
+
+        let result = self.inner.send(record, queue_timeout).await;
+
+        let span = cx.span();
+        match &result {
+            Ok((partition, offset)) => {
+                span.set_attribute(KeyValue::new(
+                    attribute::MESSAGING_DESTINATION_PARTITION_ID,
+                    partition.to_string(),
+                ));
+                span.set_attribute(KeyValue::new(attribute::MESSAGING_KAFKA_OFFSET, *offset));
+            }
+            Err((err, _)) => {
+                let error_type = error_type(err);
+                span.set_attribute(KeyValue::new(attribute::ERROR_TYPE, error_type.clone()));
+                span.set_status(Status::error(err.to_string()));
+                metric_attributes.push(KeyValue::new(attribute::ERROR_TYPE, error_type));
+            }
+        }
+        span.end();
+        self.sent_messages.add(1, &metric_attributes);
+
+        result
+    }
+}
+
+impl<C, R> fmt::Debug for InstrumentedFutureProducer<C, R>
+where
+    C: ClientContext + 'static,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("InstrumentedFutureProducer")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HeaderExtractor;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry::propagation::Extractor;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use rdkafka::message::{Header, Message, OwnedHeaders};
+    use rdkafka::ClientConfig;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn failed_send_records_error_and_keeps_headers() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let meter = SdkMeterProvider::default().meter("test");
+
+        // Nothing listens on this port, so the message times out in the local queue.
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", "127.0.0.1:1")
+            .set("message.timeout.ms", "10")
+            .create()
+            .unwrap();
+        let producer = InstrumentedFutureProducer::with_tracer_and_meter(
+            producer,
+            provider.tracer("test"),
+            &meter,
+        )
+        .with_propagator(TraceContextPropagator::new());
+
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "content-type",
+            value: Some("text/plain"),
+        });
+        let (err, message) = producer
+            .send(
+                FutureRecord::to("orders")
+                    .key("key")
+                    .payload("payload")
+                    .headers(headers),
+                Duration::from_secs(0),
+            )
+            .await
+            .unwrap_err();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        assert_eq!(spans.len(), 1);
+        let span = &spans[0];
+        assert_eq!(span.name, "publish orders");
+        assert_eq!(span.span_kind, SpanKind::Producer);
+        assert_eq!(span.status, Status::error(err.to_string()));
+        assert!(span
+            .attributes
+            .iter()
+            .any(|kv| kv.key.as_str() == attribute::ERROR_TYPE));
+
+        let headers = message.headers().unwrap();
+        let extractor = HeaderExtractor(headers);
+        assert_eq!(extractor.get("content-type"), Some("text/plain"));
+        assert_eq!(
+            extractor.get("traceparent"),
+            Some(
+                format!(
+                    "00-{}-{}-01",
+                    span.span_context.trace_id(),
+                    span.span_context.span_id()
+                )
+                .as_str()
+            )
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use opentelemetry::propagation::{Extractor, Injector};
+use rdkafka::message::{Header, Headers, OwnedHeaders};
+use std::fmt;
+
+/// Injects propagation fields into Kafka message headers.
+///
+/// [`OwnedHeaders`] can only be extended by value, so the injector takes ownership of the
+/// headers and hands them back through [`HeaderInjector::into_headers`]. Existing headers with
+/// the key of an injected field, compared case-insensitively, are replaced.
+pub struct HeaderInjector {
+    headers: Option<OwnedHeaders>,
+}
+
+impl HeaderInjector {
+    /// Create an injector appending to the given headers.
+    pub fn new(headers: OwnedHeaders) -> Self {
+        HeaderInjector {
+            headers: Some(headers),
+        }
+    }
+
+    /// Returns the headers including all injected fields.
+    pub fn into_headers(self) -> OwnedHeaders {
+        self.headers.unwrap_or_default()
+    }
+}
+
+impl Default for HeaderInjector {
+    fn default() -> Self {
+        HeaderInjector::new(OwnedHeaders::new())
+    }
+}
+
+impl Injector for HeaderInjector {
+    fn set(&mut self, key: &str, value: String) {
+        let headers = self.headers.take().unwrap_or_default();
+        let headers = if headers
+            .iter()
+            .any(|header| header.key.eq_ignore_ascii_case(key))
+        {
+            headers
+                .iter()
+                .filter(|header| !header.key.eq_ignore_ascii_case(key))
+                .fold(
+                    OwnedHeaders::new_with_capacity(headers.count()),
+                    |headers, header| headers.insert(header),
+                )
+        } else {
+            headers
+        };
+        self.headers = Some(headers.insert(Header {
+            key,
+            value: Some(&value),
+        }));
+    }
+}
+
+impl fmt::Debug for HeaderInjector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderInjector")
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+/// Extracts propagation fields from Kafka message headers.
+///
+/// Header values which are not valid UTF-8 are ignored.
+pub struct HeaderExtractor<'a, H: ?Sized>(pub &'a H);
+
+impl<H: Headers + ?Sized> HeaderExtractor<'_, H> {
+    fn headers(&self) -> impl Iterator<Item = Header<'_, &[u8]>> {
+        (0..self.0.count()).filter_map(|idx| self.0.try_get(idx))
+    }
+}
+
+impl<H: Headers + ?Sized> Extractor for HeaderExtractor<'_, H> {
+    /// Returns the value of the last header with the key, as the last header takes precedence
+    /// when a producer appended to forwarded headers.
+    fn get(&self, key: &str) -> Option<&str> {
+        self.headers()
+            .filter(|header| header.key.eq_ignore_ascii_case(key))
+            .last()
+            .and_then(|header| header.value)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.headers().map(|header| header.key).collect()
+    }
+}
+
+impl<H: ?Sized> fmt::Debug for HeaderExtractor<'_, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HeaderExtractor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::{
+        SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState,
+    };
+    use opentelemetry::Context;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+
+    #[test]
+    fn inject_and_extract_round_trip() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context.clone());
+        let propagator = TraceContextPropagator::new();
+
+        let existing = OwnedHeaders::new().insert(Header {
+            key: "content-type",
+            value: Some("application/json"),
+        });
+        let mut injector = HeaderInjector::new(existing);
+        propagator.inject_context(&cx, &mut injector);
+        let headers = injector.into_headers();
+
+        let extractor = HeaderExtractor(&headers);
+        assert_eq!(
+            extractor.keys(),
+            vec!["content-type", "traceparent", "tracestate"]
+        );
+        assert_eq!(
+            extractor.get("traceparent"),
+            Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+        );
+
+        let extracted = propagator.extract(&extractor);
+        assert_eq!(extracted.span().span_context(), &span_context);
+    }
+
+    #[test]
+    fn inject_replaces_existing_fields() {
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context.clone());
+        let propagator = TraceContextPropagator::new();
+
+        let forwarded = OwnedHeaders::new()
+            .insert(Header {
+                key: "TraceParent",
+                value: Some("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"),
+            })
+            .insert(Header {
+                key: "content-type",
+                value: Some("application/json"),
+            });
+        let mut injector = HeaderInjector::new(forwarded);
+        propagator.inject_context(&cx, &mut injector);
+        let headers = injector.into_headers();
+
+        let extractor = HeaderExtractor(&headers);
+        assert_eq!(
+            extractor.keys(),
+            vec!["content-type", "traceparent", "tracestate"]
+        );
+        let extracted = propagator.extract(&extractor);
+        assert_eq!(extracted.span().span_context(), &span_context);
+    }
+
+    #[test]
+    fn extract_uses_last_header() {
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "traceparent",
+                value: Some("stale"),
+            })
+            .insert(Header {
+                key: "Traceparent",
+                value: Some("current"),
+            });
+        assert_eq!(
+            HeaderExtractor(&headers).get("traceparent"),
+            Some("current")
+        );
+    }
+
+    #[test]
+    fn extract_ignores_non_utf8_values() {
+        let headers = OwnedHeaders::new().insert(Header {
+            key: "traceparent",
+            value: Some(&[0xff, 0xfe][..]),
+        });
+        assert_eq!(HeaderExtractor(&headers).get("traceparent"), None);
+    }
+}
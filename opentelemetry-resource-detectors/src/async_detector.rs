@@ -0,0 +1,173 @@
+//! Asynchronous resource detection
+//!
+//! Run resource detectors without blocking the async runtime.
+use opentelemetry_sdk::resource::ResourceDetector;
+use opentelemetry_sdk::Resource;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout_at, Instant};
+
+/// A resource detector which performs its I/O asynchronously, e.g. querying a metadata
+/// endpoint.
+pub trait AsyncResourceDetector: Send + Sync {
+    /// Detect the resource.
+    ///
+    /// `timeout` is the maximum time the detection is allowed to take. Detectors exceeding
+    /// it are cancelled by [`detect_resources_async`].
+    fn detect(&self, timeout: Duration) -> Pin<Box<dyn Future<Output = Resource> + Send + '_>>;
+}
+
+/// A resource detector to be run by [`detect_resources_async`].
+pub enum AnyResourceDetector {
+    /// A synchronous detector, run on the blocking thread pool of the runtime.
+    Blocking(Box<dyn ResourceDetector + Send + Sync>),
+    /// An asynchronous detector, run as a task on the runtime.
+    Async(Box<dyn AsyncResourceDetector>),
+}
+
+impl AnyResourceDetector {
+    /// Wrap a synchronous detector.
+    pub fn blocking<D: ResourceDetector + Send + Sync + 'static>(detector: D) -> Self {
+        AnyResourceDetector::Blocking(Box::new(detector))
+    }
+
+    /// Wrap an asynchronous detector.
+    pub fn from_async<D: AsyncResourceDetector + 'static>(detector: D) -> Self {
+        AnyResourceDetector::Async(Box::new(detector))
+    }
+
+    fn spawn(self, timeout: Duration) -> JoinHandle<Resource> {
+        match self {
+            AnyResourceDetector::Blocking(detector) => {
+                tokio::task::spawn_blocking(move || detector.detect(timeout))
+            }
+            AnyResourceDetector::Async(detector) => {
+                tokio::spawn(async move { detector.detect(timeout).await })
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for AnyResourceDetector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnyResourceDetector::Blocking(_) => f.write_str("AnyResourceDetector::Blocking"),
+            AnyResourceDetector::Async(_) => f.write_str("AnyResourceDetector::Async"),
+        }
+    }
+}
+
+/// Create a [`Resource`] by running the given detectors concurrently.
+///
+/// Detected resources are merged in the order of `detectors`, like
+/// [`Resource::from_detectors`] does: attributes of later detectors take priority. Detectors
+/// which have not completed within `timeout` (or panicked) contribute nothing. Blocking
+/// detectors which time out keep running on the blocking thread pool until they return.
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+pub async fn detect_resources_async(
+    detectors: Vec<AnyResourceDetector>,
+    timeout: Duration,
+) -> Resource {
+    let deadline = Instant::now() + timeout;
+    let handles: Vec<_> = detectors
+        .into_iter()
+        .map(|detector| detector.spawn(timeout))
+        .collect();
+
+    let mut resource = Resource::empty();
+    for handle in handles {
+        let abort = handle.abort_handle();
+        match timeout_at(deadline, handle).await {
+            Ok(Ok(detected)) => resource = resource.merge(&detected),
+            Ok(Err(_)) => {}
+            Err(_) => abort.abort(),
+        }
+    }
+    resource
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::{Key, KeyValue, Value};
+
+    struct StaticDetector(&'static str, &'static str);
+
+    impl ResourceDetector for StaticDetector {
+        fn detect(&self, _timeout: Duration) -> Resource {
+            Resource::new(vec![KeyValue::new(self.0, self.1)])
+        }
+    }
+
+    struct DelayedDetector(Duration, &'static str);
+
+    impl AsyncResourceDetector for DelayedDetector {
+        fn detect(
+            &self,
+            _timeout: Duration,
+        ) -> Pin<Box<dyn Future<Output = Resource> + Send + '_>> {
+            Box::pin(async move {
+                tokio::time::sleep(self.0).await;
+                Resource::new(vec![KeyValue::new("detected.by", self.1)])
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_resources_async_merges_in_order() {
+        let resource = detect_resources_async(
+            vec![
+                AnyResourceDetector::blocking(StaticDetector("detected.by", "blocking")),
+                AnyResourceDetector::blocking(StaticDetector("service.name", "test")),
+                AnyResourceDetector::from_async(DelayedDetector(Duration::ZERO, "async")),
+            ],
+            Duration::from_secs(5),
+        )
+        .await;
+
+        assert_eq!(resource.len(), 2);
+        assert_eq!(
+            resource.get(Key::from_static_str("detected.by")),
+            Some(Value::from("async"))
+        );
+        assert_eq!(
+            resource.get(Key::from_static_str("service.name")),
+            Some(Value::from("test"))
+        );
+    }
+
+    struct PendingDetector;
+
+    impl AsyncResourceDetector for PendingDetector {
+        fn detect(
+            &self,
+            _timeout: Duration,
+        ) -> Pin<Box<dyn Future<Output = Resource> + Send + '_>> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    // With the clock paused, time only advances once the blocking detector has returned and
+    // every task waits on a timer, so the outcome does not depend on scheduling.
+    #[tokio::test(start_paused = true)]
+    async fn test_detect_resources_async_skips_timed_out_detectors() {
+        let resource = detect_resources_async(
+            vec![
+                AnyResourceDetector::blocking(StaticDetector("detected.by", "blocking")),
+                AnyResourceDetector::from_async(PendingDetector),
+            ],
+            Duration::from_secs(1),
+        )
+        .await;
+
+        assert_eq!(
+            resource.get(Key::from_static_str("detected.by")),
+            Some(Value::from("blocking"))
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Telemetry distro resource detector
+//!
+//! Report the distribution of OpenTelemetry in use.
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::resource::ResourceDetector;
+use opentelemetry_sdk::Resource;
+use opentelemetry_semantic_conventions::attribute::{
+    TELEMETRY_DISTRO_NAME, TELEMETRY_DISTRO_VERSION,
+};
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Report the vendor distribution of OpenTelemetry embedding this crate.
+///
+/// This resource detector returns the following information, as provided by the distribution:
+///
+/// - distribution name (`telemetry.distro.name`)
+/// - distribution version (`telemetry.distro.version`), if set
+///
+/// ```
+/// use opentelemetry_resource_detectors::TelemetryDistroResourceDetector;
+/// use opentelemetry_sdk::Resource;
+/// use std::time::Duration;
+///
+/// let resource = Resource::from_detectors(
+///     Duration::from_secs(0),
+///     vec![Box::new(
+///         TelemetryDistroResourceDetector::new("my-distro").with_version(env!("CARGO_PKG_VERSION")),
+///     )],
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct TelemetryDistroResourceDetector {
+    name: Cow<'static, str>,
+    version: Option<Cow<'static, str>>,
+}
+
+impl TelemetryDistroResourceDetector {
+    /// Create a detector reporting the given distribution name.
+    pub fn new(name: impl Into<Cow<'static, str>>) -> Self {
+        TelemetryDistroResourceDetector {
+            name: name.into(),
+            version: None,
+        }
+    }
+
+    /// Report the given distribution version.
+    pub fn with_version(mut self, version: impl Into<Cow<'static, str>>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+}
+
+impl ResourceDetector for TelemetryDistroResourceDetector {
+    fn detect(&self, _timeout: Duration) -> Resource {
+        Resource::new(
+            [
+                Some(KeyValue::new(TELEMETRY_DISTRO_NAME, self.name.clone())),
+                self.version
+                    .clone()
+                    .map(|version| KeyValue::new(TELEMETRY_DISTRO_VERSION, version)),
+            ]
+            .into_iter()
+            .flatten(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::{Key, Value};
+
+    #[test]
+    fn test_telemetry_distro_resource_detector() {
+        let resource = TelemetryDistroResourceDetector::new("my-distro")
+            .with_version("1.2.3")
+            .detect(Duration::from_secs(0));
+        assert_eq!(resource.len(), 2);
+        assert_eq!(
+            resource.get(Key::from_static_str(TELEMETRY_DISTRO_NAME)),
+            Some(Value::from("my-distro"))
+        );
+        assert_eq!(
+            resource.get(Key::from_static_str(TELEMETRY_DISTRO_VERSION)),
+            Some(Value::from("1.2.3"))
+        );
+
+        let resource =
+            TelemetryDistroResourceDetector::new("my-distro").detect(Duration::from_secs(0));
+        assert_eq!(resource.len(), 1);
+    }
+}
@@ -9,7 +9,7 @@ use std::env::consts::ARCH;
 use std::fs::read_to_string;
 #[cfg(target_os = "linux")]
 use std::path::Path;
-#[cfg(target_os = "macos")]
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 use std::process::Command;
 use std::time::Duration;
 
@@ -17,7 +17,7 @@ use std::time::Duration;
 ///
 /// This resource detector returns the following information:
 ///
-/// - [`host.id from non-containerized systems`]: https://opentelemetry.io/docs/specs/semconv/resource/host/#collecting-hostid-from-non-containerized-systems
+/// - [`host.id from non-containerized systems`](https://opentelemetry.io/docs/specs/semconv/resource/host/#collecting-hostid-from-non-containerized-systems)
 /// - Host architecture (host.arch).
 pub struct HostResourceDetector {
     host_id_detect: fn() -> Option<String>,
@@ -74,8 +74,35 @@ fn host_id_detect() -> Option<String> {
     Some(line.split_once('=')?.1.trim().trim_matches('"').to_owned())
 }
 
-// TODO: Implement non-linux platforms
-#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+#[cfg(target_os = "windows")]
+fn host_id_detect() -> Option<String> {
+    let output = Command::new("reg")
+        .arg("query")
+        .arg(r"HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Cryptography")
+        .arg("/v")
+        .arg("MachineGuid")
+        .output()
+        .ok()?
+        .stdout;
+
+    // reg.exe writes in the console code page, the GUID itself is ASCII.
+    parse_machine_guid(&String::from_utf8_lossy(&output))
+}
+
+/// Parses the `MachineGuid` value from the output of `reg query`, e.g.
+/// `    MachineGuid    REG_SZ    <guid>`.
+#[cfg(any(target_os = "windows", test))]
+fn parse_machine_guid(output: &str) -> Option<String> {
+    let line = output.lines().find(|line| line.contains("MachineGuid"))?;
+    let mut fields = line.split_whitespace();
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some("MachineGuid"), Some("REG_SZ"), Some(guid)) => Some(guid.to_owned()),
+        _ => None,
+    }
+}
+
+// WASM targets fall back here as well, as there is no machine id to read.
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
 fn host_id_detect() -> Option<String> {
     None
 }
@@ -128,6 +155,39 @@ mod tests {
             .is_some())
     }
 
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn test_host_resource_detector_windows() {
+        let resource = HostResourceDetector::default().detect(Duration::from_secs(0));
+        assert_eq!(resource.len(), 2);
+        assert!(resource
+            .get(Key::from_static_str(
+                opentelemetry_semantic_conventions::attribute::HOST_ID
+            ))
+            .is_some());
+        assert!(resource
+            .get(Key::from_static_str(
+                opentelemetry_semantic_conventions::attribute::HOST_ARCH
+            ))
+            .is_some())
+    }
+
+    #[test]
+    fn test_parse_machine_guid() {
+        let output = "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Cryptography\r\n    \
+                      MachineGuid    REG_SZ    9f8e7c6d-1234-4abc-8def-0123456789ab\r\n\r\n";
+        assert_eq!(
+            super::parse_machine_guid(output),
+            Some("9f8e7c6d-1234-4abc-8def-0123456789ab".to_string())
+        );
+        assert_eq!(
+            super::parse_machine_guid(
+                "ERROR: The system was unable to find the specified registry key or value."
+            ),
+            None
+        );
+    }
+
     #[test]
     fn test_resource_host_arch_value() {
         let resource = HostResourceDetector::default().detect(Duration::from_secs(0));
@@ -6,10 +6,40 @@
 //! - [`OsResourceDetector`] - detect OS from runtime.
 //! - [`ProcessResourceDetector`] - detect process information.
 //! - [`HostResourceDetector`] - detect unique host ID.
+//! - [`OrchestratorResourceDetector`] - detect Nomad and Amazon ECS from environment variables.
+//! - [`TelemetryDistroResourceDetector`] - report the OpenTelemetry distribution in use.
+//!
+//! Each detector is behind the cargo feature of the same name (`os`, `process`, `host`,
+//! `orchestrator`, `telemetry-distro`), all of which are enabled by default. Disable default
+//! features to only compile the detectors you need.
+//!
+//! # Async detection
+//!
+//! With the `rt-tokio` feature, `detect_resources_async` runs synchronous detectors on
+//! Tokio's blocking thread pool and `AsyncResourceDetector`s as tasks, so resources can be
+//! detected at startup without blocking the async runtime.
+#[cfg(feature = "rt-tokio")]
+mod async_detector;
+#[cfg(feature = "telemetry-distro")]
+mod distro;
+#[cfg(feature = "host")]
 mod host;
+#[cfg(feature = "orchestrator")]
+mod orchestrator;
+#[cfg(feature = "os")]
 mod os;
+#[cfg(feature = "process")]
 mod process;
 
+#[cfg(feature = "rt-tokio")]
+pub use async_detector::{detect_resources_async, AnyResourceDetector, AsyncResourceDetector};
+#[cfg(feature = "telemetry-distro")]
+pub use distro::TelemetryDistroResourceDetector;
+#[cfg(feature = "host")]
 pub use host::HostResourceDetector;
+#[cfg(feature = "orchestrator")]
+pub use orchestrator::OrchestratorResourceDetector;
+#[cfg(feature = "os")]
 pub use os::OsResourceDetector;
+#[cfg(feature = "process")]
 pub use process::ProcessResourceDetector;
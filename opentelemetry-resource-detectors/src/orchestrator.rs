@@ -0,0 +1,127 @@
+//! Container orchestrator resource detector
+//!
+//! Detect non-Kubernetes container orchestrators from the environment variables they set.
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::resource::ResourceDetector;
+use opentelemetry_sdk::Resource;
+use opentelemetry_semantic_conventions::attribute;
+use std::env;
+use std::time::Duration;
+
+/// Detect the container orchestrator from well-known environment variables.
+///
+/// This resource detector returns the following information:
+///
+/// - [Nomad], detected by `NOMAD_ALLOC_ID`. As there are no semantic conventions for Nomad, the
+///   allocation id, task name, job name, namespace and datacenter are returned as
+///   `nomad.alloc.id`, `nomad.task.name`, `nomad.job.name`, `nomad.namespace` and
+///   `nomad.datacenter`. A Nomad task is not necessarily a container, so `container.name` is
+///   not set.
+/// - [Amazon ECS], detected by `ECS_CONTAINER_METADATA_URI_V4` or `ECS_CONTAINER_METADATA_URI`:
+///   `cloud.provider`, `cloud.platform`, `cloud.region` from `AWS_REGION` or `AWS_DEFAULT_REGION`
+///   and `aws.ecs.launchtype` from `AWS_EXECUTION_ENV`. The task metadata endpoint is not queried.
+///
+/// An empty resource is returned outside of these orchestrators.
+///
+/// [Nomad]: https://developer.hashicorp.com/nomad/docs/runtime/environment
+/// [Amazon ECS]: https://docs.aws.amazon.com/AmazonECS/latest/developerguide/task-metadata-endpoint-v4.html
+pub struct OrchestratorResourceDetector;
+
+impl ResourceDetector for OrchestratorResourceDetector {
+    fn detect(&self, _timeout: Duration) -> Resource {
+        Resource::new(orchestrator_attributes(|name| env::var(name).ok()))
+    }
+}
+
+fn orchestrator_attributes(get_env: impl Fn(&str) -> Option<String>) -> Vec<KeyValue> {
+    let get_env = |name: &str| get_env(name).filter(|value| !value.is_empty());
+    let mut attributes = Vec::new();
+
+    if let Some(alloc_id) = get_env("NOMAD_ALLOC_ID") {
+        attributes.push(KeyValue::new("nomad.alloc.id", alloc_id));
+        for (name, key) in [
+            ("NOMAD_TASK_NAME", "nomad.task.name"),
+            ("NOMAD_JOB_NAME", "nomad.job.name"),
+            ("NOMAD_NAMESPACE", "nomad.namespace"),
+            ("NOMAD_DC", "nomad.datacenter"),
+        ] {
+            if let Some(value) = get_env(name) {
+                attributes.push(KeyValue::new(key, value));
+            }
+        }
+    }
+
+    if get_env("ECS_CONTAINER_METADATA_URI_V4")
+        .or_else(|| get_env("ECS_CONTAINER_METADATA_URI"))
+        .is_some()
+    {
+        attributes.push(KeyValue::new(attribute::CLOUD_PROVIDER, "aws"));
+        attributes.push(KeyValue::new(attribute::CLOUD_PLATFORM, "aws_ecs"));
+        if let Some(region) = get_env("AWS_REGION").or_else(|| get_env("AWS_DEFAULT_REGION")) {
+            attributes.push(KeyValue::new(attribute::CLOUD_REGION, region));
+        }
+        let launch_type = match get_env("AWS_EXECUTION_ENV").as_deref() {
+            Some("AWS_ECS_FARGATE") => Some("fargate"),
+            Some("AWS_ECS_EC2") => Some("ec2"),
+            _ => None,
+        };
+        if let Some(launch_type) = launch_type {
+            attributes.push(KeyValue::new(attribute::AWS_ECS_LAUNCHTYPE, launch_type));
+        }
+    }
+
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn detect(vars: &[(&str, &str)]) -> Vec<KeyValue> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+        orchestrator_attributes(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_nomad() {
+        assert_eq!(
+            detect(&[
+                ("NOMAD_ALLOC_ID", "5456bd7a-9fc0-c0dd-6131-cbee77f57577"),
+                ("NOMAD_TASK_NAME", "api"),
+                ("NOMAD_JOB_NAME", "checkout"),
+                ("NOMAD_NAMESPACE", ""),
+            ]),
+            vec![
+                KeyValue::new("nomad.alloc.id", "5456bd7a-9fc0-c0dd-6131-cbee77f57577"),
+                KeyValue::new("nomad.task.name", "api"),
+                KeyValue::new("nomad.job.name", "checkout"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ecs() {
+        assert_eq!(
+            detect(&[
+                (
+                    "ECS_CONTAINER_METADATA_URI_V4",
+                    "http://169.254.170.2/v4/abc"
+                ),
+                ("AWS_DEFAULT_REGION", "us-west-2"),
+                ("AWS_EXECUTION_ENV", "AWS_ECS_FARGATE"),
+            ]),
+            vec![
+                KeyValue::new(attribute::CLOUD_PROVIDER, "aws"),
+                KeyValue::new(attribute::CLOUD_PLATFORM, "aws_ecs"),
+                KeyValue::new(attribute::CLOUD_REGION, "us-west-2"),
+                KeyValue::new(attribute::AWS_ECS_LAUNCHTYPE, "fargate"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_orchestrator() {
+        assert!(detect(&[("NOMAD_TASK_NAME", "api"), ("AWS_REGION", "us-west-2")]).is_empty());
+    }
+}
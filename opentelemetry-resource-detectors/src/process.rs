@@ -2,10 +2,14 @@
 //!
 //! Detect process related information like pid, executable name.
 
-use opentelemetry::{KeyValue, StringValue, Value};
+use opentelemetry::KeyValue;
+#[cfg(not(target_family = "wasm"))]
+use opentelemetry::{StringValue, Value};
 use opentelemetry_sdk::resource::ResourceDetector;
 use opentelemetry_sdk::Resource;
+#[cfg(not(target_family = "wasm"))]
 use std::env::args_os;
+#[cfg(not(target_family = "wasm"))]
 use std::process::id;
 use std::time::Duration;
 
@@ -16,28 +20,41 @@ use std::time::Duration;
 /// - process command line arguments(`process.command_args`), the full command arguments of this
 ///   application.
 /// - OS assigned process id(`process.pid`).
+///
+/// On WASM targets no process information is available and an empty resource is returned.
 pub struct ProcessResourceDetector;
 
 impl ResourceDetector for ProcessResourceDetector {
     fn detect(&self, _timeout: Duration) -> Resource {
-        let arguments = args_os();
-        let cmd_arg_val = arguments
-            .into_iter()
-            .map(|arg| arg.to_string_lossy().into_owned().into())
-            .collect::<Vec<StringValue>>();
-        Resource::new(vec![
-            KeyValue::new(
-                opentelemetry_semantic_conventions::attribute::PROCESS_COMMAND_ARGS,
-                Value::Array(cmd_arg_val.into()),
-            ),
-            KeyValue::new(
-                opentelemetry_semantic_conventions::attribute::PROCESS_PID,
-                id() as i64,
-            ),
-        ])
+        Resource::new(process_attributes())
     }
 }
 
+#[cfg(not(target_family = "wasm"))]
+fn process_attributes() -> Vec<KeyValue> {
+    let arguments = args_os();
+    let cmd_arg_val = arguments
+        .into_iter()
+        .map(|arg| arg.to_string_lossy().into_owned().into())
+        .collect::<Vec<StringValue>>();
+    vec![
+        KeyValue::new(
+            opentelemetry_semantic_conventions::attribute::PROCESS_COMMAND_ARGS,
+            Value::Array(cmd_arg_val.into()),
+        ),
+        KeyValue::new(
+            opentelemetry_semantic_conventions::attribute::PROCESS_PID,
+            id() as i64,
+        ),
+    ]
+}
+
+// `std::process::id` panics on WASM targets, so skip process detection entirely.
+#[cfg(target_family = "wasm")]
+fn process_attributes() -> Vec<KeyValue> {
+    Vec::new()
+}
+
 #[cfg(target_os = "linux")]
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,63 @@
+//! run with `$ cargo bench --bench logs -- --exact <test_name>` to run specific test for logs
+//! So to run test named "oversized_truncate" you would run `$ cargo bench --bench logs -- --exact oversized_truncate`
+//! To run all tests for logs you would run `$ cargo bench --bench logs`
+//!
+//! Events are only encoded and written while a listener is attached to the tracepoints, e.g.
+//! with `perf record -e user_events:bench_L4K1`. Without a listener the benchmarks measure the
+//! disabled path only.
+//!
+//! The `oversized_*` benchmarks emit records exceeding the 64KB user_events size limit, with
+//! the default `TruncationPolicy::Drop` and with `TruncationPolicy::Truncate`, which rebuilds the
+//! event until it fits.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use opentelemetry::logs::{AnyValue, LogRecord as _, Logger as _, LoggerProvider as _, Severity};
+use opentelemetry_sdk::logs::LoggerProvider;
+use opentelemetry_user_events_logs::{
+    ExporterConfig, ReentrantLogProcessor, TruncationPolicy, UserEventsExporter,
+};
+
+fn logger_provider(truncation_policy: TruncationPolicy) -> LoggerProvider {
+    let exporter_config = ExporterConfig {
+        truncation_policy,
+        ..Default::default()
+    };
+    let exporter = UserEventsExporter::new("bench", None, exporter_config);
+    LoggerProvider::builder()
+        .with_log_processor(ReentrantLogProcessor::new(exporter))
+        .build()
+}
+
+fn bench_emit(c: &mut Criterion, name: &str, truncation_policy: TruncationPolicy, size: usize) {
+    let provider = logger_provider(truncation_policy);
+    let logger = provider.logger("bench");
+    let body = "b".repeat(size);
+    let attribute = "a".repeat(size);
+
+    c.bench_function(name, |b| {
+        b.iter(|| {
+            let mut record = logger.create_log_record();
+            record.set_severity_number(Severity::Info);
+            record.set_body(AnyValue::String(body.clone().into()));
+            record.add_attribute("small", "value");
+            record.add_attribute("large", attribute.clone());
+            logger.emit(record);
+        })
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_emit(c, "small", TruncationPolicy::Drop, 100);
+    bench_emit(c, "oversized_drop", TruncationPolicy::Drop, 40_000);
+    bench_emit(
+        c,
+        "oversized_truncate",
+        TruncationPolicy::Truncate {
+            max_string_length: 16_000,
+        },
+        40_000,
+    );
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
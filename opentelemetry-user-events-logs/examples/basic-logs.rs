@@ -11,6 +11,7 @@ fn init_logger() -> LoggerProvider {
     let exporter_config = ExporterConfig {
         default_keyword: 1,
         keywords_map: HashMap::new(),
+        ..Default::default()
     };
     let exporter = UserEventsExporter::new("test", None, exporter_config);
     let reenterant_processor = ReentrantLogProcessor::new(exporter);
@@ -0,0 +1,92 @@
+use base64::engine::{general_purpose::STANDARD, Engine};
+use opentelemetry::logs::AnyValue;
+use opentelemetry::Key;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Converts log values to JSON. Bytes are encoded as base64 strings and map entries are
+/// ordered by key, so equal values always produce the same JSON.
+pub(super) trait IntoJson {
+    fn as_json_value(&self) -> Value;
+}
+
+impl IntoJson for AnyValue {
+    fn as_json_value(&self) -> Value {
+        match &self {
+            AnyValue::Int(value) => json!(value),
+            AnyValue::Double(value) => json!(value),
+            AnyValue::String(value) => json!(value.to_string()),
+            AnyValue::Boolean(value) => json!(value),
+            AnyValue::Bytes(value) => json!(STANDARD.encode(value.as_slice())),
+            AnyValue::ListAny(value) => value.as_json_value(),
+            AnyValue::Map(value) => value.as_json_value(),
+            &_ => Value::Null,
+        }
+    }
+}
+
+impl IntoJson for HashMap<Key, AnyValue> {
+    fn as_json_value(&self) -> Value {
+        // Sort the entries, as serde_json maps keep the insertion order if its
+        // `preserve_order` feature is enabled.
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        Value::Object(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.as_json_value()))
+                .collect::<Map<String, Value>>(),
+        )
+    }
+}
+
+impl IntoJson for [AnyValue] {
+    fn as_json_value(&self) -> Value {
+        Value::Array(self.iter().map(IntoJson::as_json_value).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_vec_of_any_value_to_json() {
+        let array = [
+            AnyValue::Int(1),
+            AnyValue::Double(-0.5),
+            AnyValue::Boolean(true),
+            AnyValue::String(r#""foo bar""#.into()),
+            AnyValue::Bytes(Box::new(vec![97u8, 98u8, 99u8])),
+            AnyValue::ListAny(Box::new(vec![AnyValue::Int(3), AnyValue::Int(4)])),
+        ];
+        let result = array.as_json_value();
+        assert_eq!(
+            result,
+            json!([1, -0.5, true, "\"foo bar\"", "YWJj", [3, 4]])
+        );
+
+        let result = [].as_json_value();
+        assert_eq!(result, json!([]));
+    }
+
+    #[test]
+    fn test_convert_map_of_any_value_to_json() {
+        let mut inner_map = HashMap::new();
+        inner_map.insert(Key::new("a"), AnyValue::Int(1));
+        inner_map.insert(Key::new("b"), AnyValue::String("b".into()));
+        let mut map = HashMap::new();
+        map.insert(Key::new("c"), AnyValue::Boolean(false));
+        map.insert(Key::new("d"), AnyValue::Map(Box::new(inner_map)));
+        map.insert(Key::new("e"), AnyValue::Map(Box::default()));
+        let result = map.as_json_value();
+        assert_eq!(
+            result,
+            json!({"c": false, "d": {"a": 1, "b": "b"}, "e": {}})
+        );
+        assert_eq!(
+            result.to_string(),
+            r#"{"c":false,"d":{"a":1,"b":"b"},"e":{}}"#
+        );
+    }
+}
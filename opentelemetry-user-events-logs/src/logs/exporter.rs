@@ -1,18 +1,50 @@
 use async_trait::async_trait;
+use base64::engine::{general_purpose::STANDARD, Engine};
 use eventheader::{FieldFormat, Level, Opcode};
-use eventheader_dynamic::EventBuilder;
+use eventheader_dynamic::{EventBuilder, EventSet};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use opentelemetry::{logs::AnyValue, logs::Severity, Key};
 use std::{cell::RefCell, str, time::SystemTime};
 
+use crate::logs::converters::IntoJson;
+
 /// Provider group associated with the user_events exporter
 pub type ProviderGroup = Option<Cow<'static, str>>;
 
 thread_local! { static EBW: RefCell<EventBuilder> = RefCell::new(EventBuilder::new());}
 
+/// Encoding of log record bodies of type [`AnyValue::Map`] and [`AnyValue::ListAny`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StructuredBodyFormat {
+    /// Export the body as a JSON string in PartB `body`.
+    #[default]
+    Json,
+    /// Export map bodies as a nested struct in PartB `body`, keeping the types of scalar values.
+    /// Lists, nested maps beyond the maximum struct depth and maps with more than 127 or no
+    /// entries are exported as JSON strings.
+    Struct,
+}
+
+/// Handling of events exceeding the 64KB size limit of user_events.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TruncationPolicy {
+    /// Drop events exceeding the size limit.
+    #[default]
+    Drop,
+    /// Rebuild events exceeding the size limit, truncating the event name, the body, PartB and
+    /// PartC string fields to `max_string_length` bytes, then omitting PartC attributes, largest first, until the
+    /// event fits. Truncated events have the PartB `truncated` field set to `true`.
+    Truncate {
+        /// Maximum length in bytes of string values of truncated events.
+        max_string_length: usize,
+    },
+}
+
 /// Exporter config
 #[derive(Debug)]
 pub struct ExporterConfig {
@@ -21,6 +53,24 @@ pub struct ExporterConfig {
     pub keywords_map: HashMap<String, u64>,
     /// default keyword if map is not defined.
     pub default_keyword: u64,
+    /// Use the observed timestamp instead of the log record timestamp for PartA `time`.
+    /// The other timestamp is exported in the PartA `ext_eventTime` or `ext_observedTime` field.
+    pub prefer_observed_time: bool,
+    /// Export the instrumentation scope name and version in the PartB `scopeName` and
+    /// `scopeVersion` fields, to distinguish logs emitted by different libraries.
+    pub include_scope: bool,
+    /// Encoding of map and list bodies.
+    pub structured_body_format: StructuredBodyFormat,
+    /// Emit each log record under its event name (the `event_name` or `name` attribute)
+    /// instead of the instrumentation scope name.
+    /// The name is sanitized to contain only ASCII alphanumerics and `_`. Records without a
+    /// usable name fall back to the instrumentation scope name.
+    pub use_record_event_name: bool,
+    /// Handling of events exceeding the user_events size limit.
+    pub truncation_policy: TruncationPolicy,
+    /// Omit PartC (log record attributes) from emitted events. `event_id` and `event_name`
+    /// attributes are still exported in PartB.
+    pub disable_part_c: bool,
 }
 
 impl Default for ExporterConfig {
@@ -28,6 +78,12 @@ impl Default for ExporterConfig {
         ExporterConfig {
             keywords_map: HashMap::new(),
             default_keyword: 1,
+            prefer_observed_time: false,
+            include_scope: false,
+            structured_body_format: StructuredBodyFormat::default(),
+            use_record_event_name: false,
+            truncation_policy: TruncationPolicy::default(),
+            disable_part_c: false,
         }
     }
 }
@@ -50,8 +106,44 @@ impl ExporterConfig {
 pub struct UserEventsExporter {
     provider: eventheader_dynamic::Provider,
     exporter_config: ExporterConfig,
+    stats: ExporterStats,
+}
+
+/// Counters of the events exceeding the user_events size limit.
+///
+/// Clones share the counters, so a handle obtained with [`UserEventsExporter::stats`] or
+/// [`ReentrantLogProcessor::stats`](crate::ReentrantLogProcessor::stats) keeps reporting
+/// after the processor is installed in a `LoggerProvider`.
+#[derive(Clone, Debug, Default)]
+pub struct ExporterStats {
+    inner: Arc<ExporterStatsInner>,
+}
+
+#[derive(Debug, Default)]
+struct ExporterStatsInner {
+    truncated_events: AtomicU64,
+    dropped_events: AtomicU64,
+}
+
+impl ExporterStats {
+    /// Returns the number of events truncated to fit the user_events size limit.
+    pub fn truncated_event_count(&self) -> u64 {
+        self.inner.truncated_events.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of events dropped for exceeding the user_events size limit, either
+    /// under [`TruncationPolicy::Drop`] or because they did not fit even after truncation.
+    pub fn dropped_event_count(&self) -> u64 {
+        self.inner.dropped_events.load(Ordering::Relaxed)
+    }
 }
 
+const EXT_EVENT_TIME: &str = "ext_eventTime";
+const EXT_OBSERVED_TIME: &str = "ext_observedTime";
+
+/// Error returned by [`EventBuilder::write`] for events exceeding the size limit.
+const ERANGE: i32 = 34;
+
 const EVENT_ID: &str = "event_id";
 const EVENT_NAME_PRIMARY: &str = "event_name";
 const EVENT_NAME_SECONDARY: &str = "name";
@@ -71,9 +163,15 @@ impl UserEventsExporter {
         UserEventsExporter {
             provider: eventheader_provider,
             exporter_config,
+            stats: ExporterStats::default(),
         }
     }
 
+    /// Returns a handle to the counters of oversized events of this exporter.
+    pub fn stats(&self) -> ExporterStats {
+        self.stats.clone()
+    }
+
     fn register_events(eventheader_provider: &mut eventheader_dynamic::Provider, keyword: u64) {
         let levels = [
             eventheader::Level::Informational,
@@ -105,7 +203,12 @@ impl UserEventsExporter {
         }
     }
 
-    fn add_attribute_to_event(&self, eb: &mut EventBuilder, (key, value): (&Key, &AnyValue)) {
+    fn add_attribute_to_event(
+        &self,
+        eb: &mut EventBuilder,
+        (key, value): (&Key, &AnyValue),
+        max_string_length: Option<usize>,
+    ) {
         let field_name = key.as_str();
         match value.to_owned() {
             AnyValue::Boolean(b) => {
@@ -118,7 +221,9 @@ impl UserEventsExporter {
                 eb.add_value(field_name, f, FieldFormat::Float, 0);
             }
             AnyValue::String(s) => {
-                eb.add_str(field_name, s.to_string(), FieldFormat::Default, 0);
+                let s = s.as_str();
+                let s = max_string_length.map_or(s, |max| truncate_str(s, max));
+                eb.add_str(field_name, s, FieldFormat::Default, 0);
             }
             _ => (),
         }
@@ -169,8 +274,8 @@ impl UserEventsExporter {
         instrumentation: &opentelemetry::InstrumentationScope,
     ) -> opentelemetry_sdk::export::logs::ExportResult {
         let mut level: Level = Level::Invalid;
-        if log_record.severity_number.is_some() {
-            level = self.get_severity_level(log_record.severity_number.unwrap());
+        if let Some(severity_number) = log_record.severity_number {
+            level = self.get_severity_level(severity_number);
         }
 
         let keyword = self
@@ -192,119 +297,516 @@ impl UserEventsExporter {
         if log_es.enabled() {
             EBW.with(|eb| {
                 let mut eb = eb.borrow_mut();
-                let event_tags: u32 = 0; // TBD name and event_tag values
-                eb.reset(instrumentation.name().as_ref(), event_tags as u16);
-                eb.opcode(Opcode::Info);
-
-                eb.add_value("__csver__", 0x0401u16, FieldFormat::HexInt, 0);
-
-                // populate CS PartA
-                let mut cs_a_count = 0;
-                let event_time: SystemTime = log_record
-                    .timestamp
-                    .or(log_record.observed_timestamp)
-                    .unwrap_or_else(SystemTime::now);
-                cs_a_count += 1; // for event_time
-                eb.add_struct("PartA", cs_a_count, 0);
-                {
-                    let time: String = chrono::DateTime::to_rfc3339(
-                        &chrono::DateTime::<chrono::Utc>::from(event_time),
-                    );
-                    eb.add_str("time", time, FieldFormat::Default, 0);
-                }
-                //populate CS PartC
-                let (mut is_event_id, mut event_id) = (false, 0);
-                let (mut is_event_name, mut event_name) = (false, "");
-                let (mut is_part_c_present, mut cs_c_bookmark, mut cs_c_count) = (false, 0, 0);
-
-                for (key, value) in log_record.attributes_iter() {
-                    match (key.as_str(), value) {
-                        (EVENT_ID, AnyValue::Int(value)) => {
-                            is_event_id = true;
-                            event_id = *value;
-                            continue;
-                        }
-                        (EVENT_NAME_PRIMARY, AnyValue::String(value)) => {
-                            is_event_name = true;
-                            event_name = value.as_str();
-                            continue;
-                        }
-                        (EVENT_NAME_SECONDARY, AnyValue::String(value)) => {
-                            if !is_event_name {
-                                event_name = value.as_str();
-                            }
-                            continue;
-                        }
-                        _ => {
-                            if !is_part_c_present {
-                                eb.add_struct_with_bookmark("PartC", 1, 0, &mut cs_c_bookmark);
-                                is_part_c_present = true;
-                            }
-                            self.add_attribute_to_event(&mut eb, (key, value));
-                            cs_c_count += 1;
-                        }
-                    }
+                self.write_log_record(&mut eb, &log_es, log_record, instrumentation, level);
+            });
+            return Ok(());
+        }
+        Ok(())
+    }
 
-                    if is_part_c_present {
-                        eb.set_struct_field_count(cs_c_bookmark, cs_c_count);
-                    }
+    /// Writes the log record to the event set. Events exceeding the user_events size limit are
+    /// rebuilt according to the configured [`TruncationPolicy`].
+    fn write_log_record(
+        &self,
+        eb: &mut EventBuilder,
+        log_es: &EventSet,
+        log_record: &opentelemetry_sdk::logs::LogRecord,
+        instrumentation: &opentelemetry::InstrumentationScope,
+        level: Level,
+    ) -> i32 {
+        let result =
+            self.write_log_record_truncated(eb, log_es, log_record, instrumentation, level);
+        if result == ERANGE {
+            self.stats
+                .inner
+                .dropped_events
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    fn write_log_record_truncated(
+        &self,
+        eb: &mut EventBuilder,
+        log_es: &EventSet,
+        log_record: &opentelemetry_sdk::logs::LogRecord,
+        instrumentation: &opentelemetry::InstrumentationScope,
+        level: Level,
+    ) -> i32 {
+        self.add_log_record_to_event(eb, log_record, instrumentation, level, None);
+        let result = eb.write(log_es, None, None);
+        let TruncationPolicy::Truncate { max_string_length } =
+            self.exporter_config.truncation_policy
+        else {
+            return result;
+        };
+        if result != ERANGE {
+            return result;
+        }
+
+        let mut truncation = Truncation {
+            max_string_length,
+            dropped_attributes: Vec::new(),
+        };
+        // Sorted by ascending size, so that the largest attribute is dropped first.
+        let mut droppable_attributes = if self.exporter_config.disable_part_c {
+            Vec::new()
+        } else {
+            get_part_c_attribute_sizes(log_record, max_string_length)
+        };
+        loop {
+            self.add_log_record_to_event(eb, log_record, instrumentation, level, Some(&truncation));
+            let result = eb.write(log_es, None, None);
+            if result != ERANGE {
+                self.stats
+                    .inner
+                    .truncated_events
+                    .fetch_add(1, Ordering::Relaxed);
+                return result;
+            }
+            match droppable_attributes.pop() {
+                Some((index, _)) => truncation.dropped_attributes.push(index),
+                None => return result,
+            }
+        }
+    }
+
+    fn add_log_record_to_event(
+        &self,
+        eb: &mut EventBuilder,
+        log_record: &opentelemetry_sdk::logs::LogRecord,
+        instrumentation: &opentelemetry::InstrumentationScope,
+        level: Level,
+        truncation: Option<&Truncation>,
+    ) {
+        let event_tags: u32 = 0; // TBD name and event_tag values
+        let event_name = if self.exporter_config.use_record_event_name {
+            get_event_name(log_record, instrumentation.name())
+        } else {
+            Cow::Borrowed(instrumentation.name())
+        };
+        let event_name = match truncation {
+            Some(truncation) => truncate_str(&event_name, truncation.max_string_length),
+            None => &event_name,
+        };
+        eb.reset(event_name, event_tags as u16);
+        eb.opcode(Opcode::Info);
+
+        eb.add_value("__csver__", 0x0401u16, FieldFormat::HexInt, 0);
+
+        // populate CS PartA
+        let part_a_fields =
+            get_part_a_fields(log_record, self.exporter_config.prefer_observed_time);
+        eb.add_struct("PartA", part_a_fields.len() as u8, 0);
+        for (name, value) in part_a_fields {
+            eb.add_str(name, value, FieldFormat::Default, 0);
+        }
+        //populate CS PartC
+        let (mut is_event_id, mut event_id) = (false, 0);
+        let (mut is_event_name, mut event_name) = (false, "");
+        let (mut is_part_c_present, mut cs_c_bookmark, mut cs_c_count) = (false, 0, 0);
+
+        for (index, (key, value)) in log_record.attributes_iter().enumerate() {
+            match (key.as_str(), value) {
+                (EVENT_ID, AnyValue::Int(value)) => {
+                    is_event_id = true;
+                    event_id = *value;
+                    continue;
                 }
-                // populate CS PartB
-                let mut cs_b_bookmark: usize = 0;
-                let mut cs_b_count = 0;
-                eb.add_struct_with_bookmark("PartB", 1, 0, &mut cs_b_bookmark);
-                eb.add_str("_typeName", "Logs", FieldFormat::Default, 0);
-                cs_b_count += 1;
-
-                if log_record.body.is_some() {
-                    eb.add_str(
-                        "body",
-                        match log_record.body.as_ref().unwrap() {
-                            AnyValue::Int(value) => value.to_string(),
-                            AnyValue::String(value) => value.to_string(),
-                            AnyValue::Boolean(value) => value.to_string(),
-                            AnyValue::Double(value) => value.to_string(),
-                            AnyValue::Bytes(value) => String::from_utf8_lossy(value).to_string(),
-                            AnyValue::ListAny(_value) => "".to_string(),
-                            AnyValue::Map(_value) => "".to_string(),
-                            &_ => "".to_string(),
-                        },
-                        FieldFormat::Default,
-                        0,
-                    );
-                    cs_b_count += 1;
+                (EVENT_NAME_PRIMARY, AnyValue::String(value)) => {
+                    is_event_name = true;
+                    event_name = value.as_str();
+                    continue;
                 }
-                if level != Level::Invalid {
-                    eb.add_value("severityNumber", level.as_int(), FieldFormat::SignedInt, 0);
-                    cs_b_count += 1;
+                (EVENT_NAME_SECONDARY, AnyValue::String(value)) => {
+                    if !is_event_name {
+                        event_name = value.as_str();
+                    }
+                    continue;
                 }
-                if log_record.severity_text.is_some() {
-                    eb.add_str(
-                        "severityText",
-                        log_record.severity_text.as_ref().unwrap(),
-                        FieldFormat::SignedInt,
-                        0,
+                _ => {
+                    // Attributes other than event id/name are only exported in PartC.
+                    if self.exporter_config.disable_part_c {
+                        continue;
+                    }
+                    if truncation.is_some_and(|t| t.dropped_attributes.contains(&index)) {
+                        continue;
+                    }
+                    if !is_part_c_present {
+                        eb.add_struct_with_bookmark("PartC", 1, 0, &mut cs_c_bookmark);
+                        is_part_c_present = true;
+                    }
+                    self.add_attribute_to_event(
+                        eb,
+                        (key, value),
+                        truncation.map(|t| t.max_string_length),
                     );
-                    cs_b_count += 1;
-                }
-                if is_event_id {
-                    eb.add_value("eventId", event_id, FieldFormat::SignedInt, 0);
-                    cs_b_count += 1;
+                    cs_c_count += 1;
                 }
-                if !event_name.is_empty() {
-                    eb.add_str("name", event_name, FieldFormat::Default, 0);
-                    cs_b_count += 1;
-                }
-                eb.set_struct_field_count(cs_b_bookmark, cs_b_count);
+            }
+
+            if is_part_c_present {
+                eb.set_struct_field_count(cs_c_bookmark, cs_c_count);
+            }
+        }
+        // populate CS PartB
+        let mut cs_b_bookmark: usize = 0;
+        let mut cs_b_count = 0;
+        eb.add_struct_with_bookmark("PartB", 1, 0, &mut cs_b_bookmark);
+        eb.add_str("_typeName", "Logs", FieldFormat::Default, 0);
+        cs_b_count += 1;
 
-                eb.write(&log_es, None, None);
+        if let Some(body) = log_record.body.as_ref() {
+            add_body_to_event(
+                eb,
+                body,
+                self.exporter_config.structured_body_format,
+                truncation.map(|t| t.max_string_length),
+            );
+            cs_b_count += 1;
+        }
+        if level != Level::Invalid {
+            eb.add_value("severityNumber", level.as_int(), FieldFormat::SignedInt, 0);
+            cs_b_count += 1;
+        }
+        if let Some(severity_text) = log_record.severity_text {
+            let severity_text = truncation.map_or(severity_text, |t| {
+                truncate_str(severity_text, t.max_string_length)
             });
-            return Ok(());
+            eb.add_str("severityText", severity_text, FieldFormat::SignedInt, 0);
+            cs_b_count += 1;
+        }
+        if is_event_id {
+            eb.add_value("eventId", event_id, FieldFormat::SignedInt, 0);
+            cs_b_count += 1;
+        }
+        if !event_name.is_empty() {
+            let event_name = truncation.map_or(event_name, |t| {
+                truncate_str(event_name, t.max_string_length)
+            });
+            eb.add_str("name", event_name, FieldFormat::Default, 0);
+            cs_b_count += 1;
+        }
+        for (name, value) in
+            get_part_b_scope_fields(instrumentation, self.exporter_config.include_scope)
+        {
+            eb.add_str(name, value, FieldFormat::Default, 0);
+            cs_b_count += 1;
+        }
+        if truncation.is_some() {
+            eb.add_value("truncated", true, FieldFormat::Boolean, 0);
+            cs_b_count += 1;
+        }
+        eb.set_struct_field_count(cs_b_bookmark, cs_b_count);
+    }
+}
+
+/// State of an event being rebuilt to fit the user_events size limit.
+struct Truncation {
+    max_string_length: usize,
+    /// Indexes of the log record attributes omitted from PartC.
+    dropped_attributes: Vec<usize>,
+}
+
+/// Returns the index and the estimated encoded size of the log record attributes exported in
+/// PartC, sorted by ascending size.
+fn get_part_c_attribute_sizes(
+    log_record: &opentelemetry_sdk::logs::LogRecord,
+    max_string_length: usize,
+) -> Vec<(usize, usize)> {
+    let mut sizes: Vec<(usize, usize)> = log_record
+        .attributes_iter()
+        .enumerate()
+        .filter(|(_, (key, value))| {
+            !matches!(
+                (key.as_str(), value),
+                (EVENT_ID, AnyValue::Int(_))
+                    | (
+                        EVENT_NAME_PRIMARY | EVENT_NAME_SECONDARY,
+                        AnyValue::String(_)
+                    )
+            )
+        })
+        .map(|(index, (key, value))| {
+            let value_size = match value {
+                AnyValue::String(s) => s.as_str().len().min(max_string_length),
+                _ => 8,
+            };
+            (index, key.as_str().len() + value_size)
+        })
+        .collect();
+    sizes.sort_by_key(|&(_, size)| size);
+    sizes
+}
+
+/// Returns the longest prefix of `s` of at most `max_length` bytes ending on a char boundary.
+fn truncate_str(s: &str, max_length: usize) -> &str {
+    if s.len() <= max_length {
+        return s;
+    }
+    let mut end = max_length;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Returns the sanitized `event_name` (or `name`) attribute of the log record, or `default` if
+/// the record has no usable event name.
+fn get_event_name<'a>(
+    log_record: &'a opentelemetry_sdk::logs::LogRecord,
+    default: &'a str,
+) -> Cow<'a, str> {
+    let mut event_name = None;
+    for (key, value) in log_record.attributes_iter() {
+        match (key.as_str(), value) {
+            (EVENT_NAME_PRIMARY, AnyValue::String(value)) => {
+                event_name = Some(value.as_str());
+                break;
+            }
+            (EVENT_NAME_SECONDARY, AnyValue::String(value)) if event_name.is_none() => {
+                event_name = Some(value.as_str());
+            }
+            _ => {}
+        }
+    }
+
+    match event_name.map(sanitize_event_name) {
+        Some(event_name) if !event_name.is_empty() => event_name,
+        _ => Cow::Borrowed(default),
+    }
+}
+
+/// Replaces every character which is not an ASCII alphanumeric or `_` with `_`.
+fn sanitize_event_name(name: &str) -> Cow<'_, str> {
+    let is_valid = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    if name.chars().all(is_valid) {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(
+            name.chars()
+                .map(|c| if is_valid(c) { c } else { '_' })
+                .collect(),
+        )
+    }
+}
+
+/// Maximum nesting of structs below PartB `body` for [`StructuredBodyFormat::Struct`].
+const MAX_BODY_STRUCT_DEPTH: usize = 4;
+
+/// Adds the log record body as the PartB `body` field, truncating strings to
+/// `max_string_length` bytes if set.
+fn add_body_to_event(
+    eb: &mut EventBuilder,
+    body: &AnyValue,
+    format: StructuredBodyFormat,
+    max_string_length: Option<usize>,
+) {
+    match (get_body_encoding(body, format), body) {
+        (BodyEncoding::Struct, AnyValue::Map(map)) => {
+            add_map_as_struct(eb, "body", map, 1, max_string_length);
+        }
+        (BodyEncoding::Json, _) => {
+            add_json_str(eb, "body", body, max_string_length);
+        }
+        (_, body) => add_scalar_body_to_event(eb, body, max_string_length),
+    }
+}
+
+/// Encoding of the PartB `body` field.
+#[derive(Debug, PartialEq, Eq)]
+enum BodyEncoding {
+    /// Nested struct, for map bodies with [`StructuredBodyFormat::Struct`].
+    Struct,
+    /// JSON string.
+    Json,
+    /// Plain string.
+    String,
+}
+
+fn get_body_encoding(body: &AnyValue, format: StructuredBodyFormat) -> BodyEncoding {
+    match body {
+        AnyValue::Map(map)
+            if format == StructuredBodyFormat::Struct && is_struct_compatible(map) =>
+        {
+            BodyEncoding::Struct
+        }
+        AnyValue::Map(_) | AnyValue::ListAny(_) => BodyEncoding::Json,
+        _ => BodyEncoding::String,
+    }
+}
+
+fn add_scalar_body_to_event(
+    eb: &mut EventBuilder,
+    body: &AnyValue,
+    max_string_length: Option<usize>,
+) {
+    match body {
+        AnyValue::Int(value) => {
+            eb.add_str("body", value.to_string(), FieldFormat::Default, 0);
+        }
+        AnyValue::String(value) => {
+            let value = value.as_str();
+            let value = max_string_length.map_or(value, |max| truncate_str(value, max));
+            eb.add_str("body", value, FieldFormat::Default, 0);
+        }
+        AnyValue::Boolean(value) => {
+            eb.add_str("body", value.to_string(), FieldFormat::Default, 0);
+        }
+        AnyValue::Double(value) => {
+            eb.add_str("body", value.to_string(), FieldFormat::Default, 0);
+        }
+        AnyValue::Bytes(value) => {
+            let value = STANDARD.encode(value.as_slice());
+            let value = max_string_length.map_or(value.as_str(), |max| truncate_str(&value, max));
+            eb.add_str("body", value, FieldFormat::Default, 0);
+        }
+        &_ => {
+            eb.add_str("body", "", FieldFormat::Default, 0);
+        }
+    }
+}
+
+/// Adds the value as a JSON string. JSON longer than `max_string_length` is truncated and, as it
+/// is no longer valid JSON, added as a plain string.
+fn add_json_str(
+    eb: &mut EventBuilder,
+    field_name: &str,
+    value: &AnyValue,
+    max_string_length: Option<usize>,
+) {
+    let json = value.as_json_value().to_string();
+    match max_string_length {
+        Some(max) if json.len() > max => {
+            eb.add_str(
+                field_name,
+                truncate_str(&json, max),
+                FieldFormat::Default,
+                0,
+            );
+        }
+        _ => {
+            eb.add_str(field_name, json, FieldFormat::StringJson, 0);
         }
-        Ok(())
     }
 }
 
+/// EventHeader structs hold between 1 and 127 fields.
+fn is_struct_compatible(map: &HashMap<Key, AnyValue>) -> bool {
+    (1..=127).contains(&map.len())
+}
+
+fn add_map_as_struct(
+    eb: &mut EventBuilder,
+    name: &str,
+    map: &HashMap<Key, AnyValue>,
+    depth: usize,
+    max_string_length: Option<usize>,
+) {
+    eb.add_struct(name, map.len() as u8, 0);
+    // Sort the fields, so that equal maps always produce the same event.
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+    for (key, value) in entries {
+        let field_name = key.as_str();
+        match value {
+            AnyValue::Map(map) if depth < MAX_BODY_STRUCT_DEPTH && is_struct_compatible(map) => {
+                add_map_as_struct(eb, field_name, map, depth + 1, max_string_length);
+            }
+            AnyValue::Map(_) | AnyValue::ListAny(_) => {
+                add_json_str(eb, field_name, value, max_string_length);
+            }
+            AnyValue::Boolean(b) => {
+                eb.add_value(field_name, *b, FieldFormat::Boolean, 0);
+            }
+            AnyValue::Int(i) => {
+                eb.add_value(field_name, *i, FieldFormat::SignedInt, 0);
+            }
+            AnyValue::Double(f) => {
+                eb.add_value(field_name, *f, FieldFormat::Float, 0);
+            }
+            AnyValue::String(s) => {
+                let s = s.as_str();
+                let s = max_string_length.map_or(s, |max| truncate_str(s, max));
+                eb.add_str(field_name, s, FieldFormat::Default, 0);
+            }
+            AnyValue::Bytes(b) => {
+                let b = STANDARD.encode(b.as_slice());
+                let b = max_string_length.map_or(b.as_str(), |max| truncate_str(&b, max));
+                eb.add_str(field_name, b, FieldFormat::Default, 0);
+            }
+            &_ => {
+                // Keep the field count of the struct consistent.
+                eb.add_str(field_name, "", FieldFormat::Default, 0);
+            }
+        }
+    }
+}
+
+/// Returns the names and values of the PartB `scopeName` and `scopeVersion` fields, if
+/// `include_scope` is set.
+fn get_part_b_scope_fields(
+    instrumentation: &opentelemetry::InstrumentationScope,
+    include_scope: bool,
+) -> Vec<(&'static str, &str)> {
+    let mut fields = Vec::new();
+    if include_scope {
+        fields.push(("scopeName", instrumentation.name()));
+        if let Some(version) = instrumentation.version() {
+            fields.push(("scopeVersion", version));
+        }
+    }
+    fields
+}
+
+/// Returns the names and values of the PartA fields: `time`, the other timestamp of the record,
+/// if set, and `ext_dt_traceId` and `ext_dt_spanId` if the record has a trace context.
+fn get_part_a_fields(
+    log_record: &opentelemetry_sdk::logs::LogRecord,
+    prefer_observed_time: bool,
+) -> Vec<(&'static str, String)> {
+    let (event_time, ext_time) = get_part_a_times(log_record, prefer_observed_time);
+    let mut fields = vec![("time", format_time(event_time))];
+    if let Some((name, time)) = ext_time {
+        fields.push((name, format_time(time)));
+    }
+    if let Some(trace_context) = &log_record.trace_context {
+        fields.push(("ext_dt_traceId", trace_context.trace_id.to_string()));
+        fields.push(("ext_dt_spanId", trace_context.span_id.to_string()));
+    }
+    fields
+}
+
+/// Returns the timestamp for PartA `time`, and the other timestamp of the record, if set,
+/// together with its PartA field name.
+fn get_part_a_times(
+    log_record: &opentelemetry_sdk::logs::LogRecord,
+    prefer_observed_time: bool,
+) -> (SystemTime, Option<(&'static str, SystemTime)>) {
+    let (primary, secondary, secondary_name) = if prefer_observed_time {
+        (
+            log_record.observed_timestamp,
+            log_record.timestamp,
+            EXT_EVENT_TIME,
+        )
+    } else {
+        (
+            log_record.timestamp,
+            log_record.observed_timestamp,
+            EXT_OBSERVED_TIME,
+        )
+    };
+    match primary {
+        Some(time) => (time, secondary.map(|secondary| (secondary_name, secondary))),
+        None => (secondary.unwrap_or_else(SystemTime::now), None),
+    }
+}
+
+/// Formats the time as RFC 3339, keeping its full (up to nanosecond) precision.
+fn format_time(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
 impl Debug for UserEventsExporter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("user_events log exporter")
@@ -346,3 +848,348 @@ impl opentelemetry_sdk::export::logs::LogExporter for UserEventsExporter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_get_part_a_times() {
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789);
+        let observed = timestamp + Duration::from_secs(5);
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.timestamp = Some(timestamp);
+        record.observed_timestamp = Some(observed);
+
+        assert_eq!(
+            get_part_a_times(&record, false),
+            (timestamp, Some((EXT_OBSERVED_TIME, observed)))
+        );
+        assert_eq!(
+            get_part_a_times(&record, true),
+            (observed, Some((EXT_EVENT_TIME, timestamp)))
+        );
+
+        record.observed_timestamp = None;
+        assert_eq!(get_part_a_times(&record, true), (timestamp, None));
+    }
+
+    #[test]
+    fn test_get_part_b_scope_fields() {
+        let scope = opentelemetry::InstrumentationScope::builder("scope")
+            .with_version("1.0")
+            .build();
+        assert!(get_part_b_scope_fields(&scope, false).is_empty());
+        assert_eq!(
+            get_part_b_scope_fields(&scope, true),
+            vec![("scopeName", "scope"), ("scopeVersion", "1.0")]
+        );
+
+        let scope = opentelemetry::InstrumentationScope::builder("scope").build();
+        assert_eq!(
+            get_part_b_scope_fields(&scope, true),
+            vec![("scopeName", "scope")]
+        );
+    }
+
+    #[test]
+    fn test_add_body_to_event() {
+        let mut inner = HashMap::new();
+        inner.insert(Key::new("b"), AnyValue::Boolean(true));
+        let mut map = HashMap::new();
+        map.insert(Key::new("a"), AnyValue::Int(1));
+        map.insert(Key::new("inner"), AnyValue::Map(Box::new(inner)));
+        map.insert(Key::new("empty"), AnyValue::Map(Box::default()));
+        map.insert(
+            Key::new("list"),
+            AnyValue::ListAny(Box::new(vec![AnyValue::Double(1.5)])),
+        );
+        // Body and expected encoding with StructuredBodyFormat::Json and ::Struct.
+        let bodies = [
+            (
+                AnyValue::Map(Box::new(map)),
+                BodyEncoding::Json,
+                BodyEncoding::Struct,
+            ),
+            (
+                AnyValue::Map(Box::default()),
+                BodyEncoding::Json,
+                BodyEncoding::Json,
+            ),
+            (
+                AnyValue::ListAny(Box::new(vec![AnyValue::String("a".into())])),
+                BodyEncoding::Json,
+                BodyEncoding::Json,
+            ),
+            (
+                AnyValue::String("message".into()),
+                BodyEncoding::String,
+                BodyEncoding::String,
+            ),
+            (
+                AnyValue::Bytes(Box::new(vec![0xff])),
+                BodyEncoding::String,
+                BodyEncoding::String,
+            ),
+        ];
+
+        for (body, json_encoding, struct_encoding) in bodies {
+            assert_eq!(
+                get_body_encoding(&body, StructuredBodyFormat::Json),
+                json_encoding
+            );
+            assert_eq!(
+                get_body_encoding(&body, StructuredBodyFormat::Struct),
+                struct_encoding
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncate_str() {
+        assert_eq!(truncate_str("abc", 5), "abc");
+        assert_eq!(truncate_str("abcdef", 3), "abc");
+        // "é" is two bytes long and must not be split.
+        assert_eq!(truncate_str("aé", 2), "a");
+    }
+
+    #[test]
+    fn test_write_log_record_truncation() {
+        use opentelemetry::logs::LogRecord as _;
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.set_body(AnyValue::String("b".repeat(40_000).into()));
+        record.add_attribute("small", "s");
+        record.add_attribute("large", "l".repeat(40_000));
+        record.add_attribute("event_name", "e".repeat(40_000));
+        let scope = opentelemetry::InstrumentationScope::builder("test").build();
+
+        let mut provider = eventheader_dynamic::Provider::new(
+            "test_truncation",
+            &eventheader_dynamic::Provider::new_options(),
+        );
+        let log_es = provider.register_set(Level::Informational, 1);
+        let mut eb = EventBuilder::new();
+
+        let exporter = UserEventsExporter::new("test", None, ExporterConfig::default());
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_eq!(result, ERANGE);
+        assert_eq!(exporter.stats().truncated_event_count(), 0);
+        assert_eq!(exporter.stats().dropped_event_count(), 1);
+
+        // The body and the PartB name still exceed the size limit after truncation.
+        let exporter = UserEventsExporter::new(
+            "test",
+            None,
+            ExporterConfig {
+                truncation_policy: TruncationPolicy::Truncate {
+                    max_string_length: 40_000,
+                },
+                ..Default::default()
+            },
+        );
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_eq!(result, ERANGE);
+        assert_eq!(exporter.stats().truncated_event_count(), 0);
+        assert_eq!(exporter.stats().dropped_event_count(), 1);
+
+        // The event fits once the large PartC attribute is omitted.
+        let exporter = UserEventsExporter::new(
+            "test",
+            None,
+            ExporterConfig {
+                truncation_policy: TruncationPolicy::Truncate {
+                    max_string_length: 30_000,
+                },
+                ..Default::default()
+            },
+        );
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_ne!(result, ERANGE);
+        assert_eq!(exporter.stats().truncated_event_count(), 1);
+        assert_eq!(exporter.stats().dropped_event_count(), 0);
+
+        let exporter = UserEventsExporter::new(
+            "test",
+            None,
+            ExporterConfig {
+                truncation_policy: TruncationPolicy::Truncate {
+                    max_string_length: 10_000,
+                },
+                ..Default::default()
+            },
+        );
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_ne!(result, ERANGE);
+        assert_eq!(exporter.stats().truncated_event_count(), 1);
+        assert_eq!(exporter.stats().dropped_event_count(), 0);
+    }
+
+    #[test]
+    fn test_stats_after_installing_processor() {
+        use crate::ReentrantLogProcessor;
+        use opentelemetry::logs::LogRecord as _;
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.set_body(AnyValue::String("b".repeat(70_000).into()));
+        let scope = opentelemetry::InstrumentationScope::builder("test").build();
+
+        let mut provider = eventheader_dynamic::Provider::new(
+            "test_stats",
+            &eventheader_dynamic::Provider::new_options(),
+        );
+        let log_es = provider.register_set(Level::Informational, 1);
+        let mut eb = EventBuilder::new();
+
+        // Without a listener attached to the tracepoints, the logger provider does not write
+        // any event, so the oversized event is written to the test event set directly.
+        let exporter = UserEventsExporter::new("test", None, ExporterConfig::default());
+        let stats = exporter.stats();
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_eq!(result, ERANGE);
+
+        let processor = ReentrantLogProcessor::new(exporter);
+        let processor_stats = processor.stats();
+        let logger_provider = opentelemetry_sdk::logs::LoggerProvider::builder()
+            .with_log_processor(processor)
+            .build();
+
+        assert_eq!(stats.dropped_event_count(), 1);
+        assert_eq!(processor_stats.dropped_event_count(), 1);
+        assert_eq!(processor_stats.truncated_event_count(), 0);
+        logger_provider.shutdown().unwrap();
+        assert_eq!(processor_stats.dropped_event_count(), 1);
+    }
+
+    #[test]
+    fn test_get_part_c_attribute_sizes() {
+        use opentelemetry::logs::LogRecord as _;
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.add_attribute("event_id", 1);
+        record.add_attribute("large", "l".repeat(100));
+        record.add_attribute("int", 1);
+        record.add_attribute("small", "s");
+        assert_eq!(
+            get_part_c_attribute_sizes(&record, 50),
+            vec![(3, 6), (2, 11), (1, 55)]
+        );
+    }
+
+    #[test]
+    fn test_disable_part_c() {
+        use opentelemetry::logs::LogRecord as _;
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.add_attribute("large", "l".repeat(70_000));
+        let scope = opentelemetry::InstrumentationScope::builder("test").build();
+
+        let mut provider = eventheader_dynamic::Provider::new(
+            "test_disable_part_c",
+            &eventheader_dynamic::Provider::new_options(),
+        );
+        let log_es = provider.register_set(Level::Informational, 1);
+        let mut eb = EventBuilder::new();
+
+        let exporter = UserEventsExporter::new("test", None, ExporterConfig::default());
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_eq!(result, ERANGE);
+
+        // The oversized attribute is only exported in PartC.
+        let exporter = UserEventsExporter::new(
+            "test",
+            None,
+            ExporterConfig {
+                disable_part_c: true,
+                ..Default::default()
+            },
+        );
+        let result =
+            exporter.write_log_record(&mut eb, &log_es, &record, &scope, Level::Informational);
+        assert_ne!(result, ERANGE);
+    }
+
+    #[test]
+    fn test_get_part_a_fields() {
+        use opentelemetry::logs::LogRecord as _;
+        use opentelemetry::trace::{SpanId, TraceId};
+
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.timestamp = Some(timestamp);
+        assert_eq!(
+            get_part_a_fields(&record, false),
+            vec![("time", "2023-11-14T22:13:20+00:00".to_string())]
+        );
+
+        record.set_trace_context(
+            TraceId::from_bytes([1; 16]),
+            SpanId::from_bytes([2; 8]),
+            None,
+        );
+        assert_eq!(
+            get_part_a_fields(&record, false),
+            vec![
+                ("time", "2023-11-14T22:13:20+00:00".to_string()),
+                (
+                    "ext_dt_traceId",
+                    "01010101010101010101010101010101".to_string()
+                ),
+                ("ext_dt_spanId", "0202020202020202".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_struct_compatible() {
+        let mut map = HashMap::new();
+        assert!(!is_struct_compatible(&map));
+        for i in 0..127 {
+            map.insert(Key::new(i.to_string()), AnyValue::Int(i));
+        }
+        assert!(is_struct_compatible(&map));
+        map.insert(Key::new("overflow"), AnyValue::Int(0));
+        assert!(!is_struct_compatible(&map));
+    }
+
+    #[test]
+    fn test_get_event_name() {
+        use opentelemetry::logs::LogRecord as _;
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        assert_eq!(get_event_name(&record, "scope"), "scope");
+
+        record.add_attribute("name", "secondary");
+        assert_eq!(get_event_name(&record, "scope"), "secondary");
+
+        record.add_attribute("event_name", "my-event.name");
+        assert_eq!(get_event_name(&record, "scope"), "my_event_name");
+
+        let mut record: opentelemetry_sdk::logs::LogRecord = Default::default();
+        record.add_attribute("event_name", "");
+        assert_eq!(get_event_name(&record, "scope"), "scope");
+    }
+
+    #[test]
+    fn test_sanitize_event_name() {
+        assert!(matches!(
+            sanitize_event_name("Valid_Name1"),
+            Cow::Borrowed("Valid_Name1")
+        ));
+        assert_eq!(sanitize_event_name("a b/c-d"), "a_b_c_d");
+        assert_eq!(sanitize_event_name(""), "");
+    }
+
+    #[test]
+    fn test_format_time_keeps_precision() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789);
+        assert_eq!(format_time(time), "2023-11-14T22:13:20.123456789+00:00");
+    }
+}
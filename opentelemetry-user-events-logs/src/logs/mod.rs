@@ -3,3 +3,5 @@ pub use exporter::*;
 
 mod reentrant_logprocessor;
 pub use reentrant_logprocessor::*;
+
+mod converters;
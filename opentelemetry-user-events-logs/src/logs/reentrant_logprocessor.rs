@@ -23,6 +23,13 @@ impl ReentrantLogProcessor {
             event_exporter: exporter,
         }
     }
+
+    /// Returns a handle to the counters of truncated and dropped oversized events, which
+    /// remains usable after the processor is installed in a `LoggerProvider`.
+    /// See [`ExporterConfig::truncation_policy`].
+    pub fn stats(&self) -> ExporterStats {
+        self.event_exporter.stats()
+    }
 }
 
 impl opentelemetry_sdk::logs::LogProcessor for ReentrantLogProcessor {
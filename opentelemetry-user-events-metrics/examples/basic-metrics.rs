@@ -1,29 +1,24 @@
 //! run with `$ cargo run --example basic --all-features
 use opentelemetry::{metrics::MeterProvider as _, KeyValue};
-use opentelemetry_sdk::{
-    metrics::{PeriodicReader, SdkMeterProvider},
-    runtime, Resource,
-};
-use opentelemetry_user_events_metrics::MetricsExporter;
+use opentelemetry_sdk::{metrics::SdkMeterProvider, Resource};
+use opentelemetry_user_events_metrics::UserEventsMeterProviderBuilderExt;
 use std::thread;
 use std::time::Duration;
 
-fn init_metrics(exporter: MetricsExporter) -> SdkMeterProvider {
-    let reader = PeriodicReader::builder(exporter, runtime::Tokio).build();
+fn init_metrics() -> SdkMeterProvider {
     SdkMeterProvider::builder()
         .with_resource(Resource::new(vec![KeyValue::new(
             "service.name",
             "metric-demo",
         )]))
-        .with_reader(reader)
+        .with_user_events_exporter()
         .build()
 }
 
 #[tokio::main]
 #[allow(unused_must_use)]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let exporter = opentelemetry_user_events_metrics::MetricsExporter::new();
-    let meter_provider = init_metrics(exporter);
+    let meter_provider = init_metrics();
 
     let meter = meter_provider.meter("user-event-test");
 
@@ -0,0 +1,103 @@
+use crate::MetricsExporter;
+use opentelemetry_sdk::metrics::exporter::PushMetricExporter;
+use opentelemetry_sdk::metrics::{MeterProviderBuilder, PeriodicReader};
+use opentelemetry_sdk::runtime;
+
+/// Extension trait to export metrics of a [`MeterProviderBuilder`] to user_events.
+///
+/// The metrics are collected by a [`PeriodicReader`] running on the Tokio runtime, so the
+/// provider must be built within a Tokio runtime, which must keep running while metrics are
+/// exported. Applications using another runtime need to build the [`PeriodicReader`] for the
+/// [`MetricsExporter`] themselves.
+///
+/// The exporter writes to the fixed `otlp_metrics` tracepoint, so unlike the logs and trace
+/// builder extensions, these methods take no provider name.
+///
+/// ```no_run
+/// use opentelemetry_sdk::metrics::SdkMeterProvider;
+/// use opentelemetry_user_events_metrics::UserEventsMeterProviderBuilderExt;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let provider = SdkMeterProvider::builder()
+///     .with_user_events_exporter()
+///     .build();
+/// # }
+/// ```
+pub trait UserEventsMeterProviderBuilderExt {
+    /// Export metrics with a default [`MetricsExporter`], collected by a [`PeriodicReader`]
+    /// running on the Tokio runtime.
+    fn with_user_events_exporter(self) -> Self;
+
+    /// Export metrics with the given, e.g. filtered, [`MetricsExporter`], collected by a
+    /// [`PeriodicReader`] running on the Tokio runtime.
+    fn with_user_events_exporter_instance(self, exporter: MetricsExporter) -> Self;
+}
+
+impl UserEventsMeterProviderBuilderExt for MeterProviderBuilder {
+    fn with_user_events_exporter(self) -> Self {
+        self.with_user_events_exporter_instance(MetricsExporter::new())
+    }
+
+    fn with_user_events_exporter_instance(self, exporter: MetricsExporter) -> Self {
+        with_periodic_reader(self, exporter)
+    }
+}
+
+fn with_periodic_reader<E>(builder: MeterProviderBuilder, exporter: E) -> MeterProviderBuilder
+where
+    E: PushMetricExporter,
+{
+    builder.with_reader(PeriodicReader::builder(exporter, runtime::Tokio).build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::data::ResourceMetrics;
+    use opentelemetry_sdk::metrics::{MetricResult, SdkMeterProvider, Temporality};
+    use std::sync::{Arc, Mutex};
+
+    /// Records the names of the exported metrics.
+    #[derive(Clone, Debug, Default)]
+    struct RecordingExporter(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait]
+    impl PushMetricExporter for RecordingExporter {
+        async fn export(&self, metrics: &mut ResourceMetrics) -> MetricResult<()> {
+            let mut names = self.0.lock().unwrap();
+            for scope_metrics in &metrics.scope_metrics {
+                names.extend(scope_metrics.metrics.iter().map(|m| m.name.to_string()));
+            }
+            Ok(())
+        }
+
+        async fn force_flush(&self) -> MetricResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> MetricResult<()> {
+            Ok(())
+        }
+
+        fn temporality(&self) -> Temporality {
+            Temporality::Delta
+        }
+    }
+
+    // Flushing the periodic reader blocks the calling thread until the reader task, spawned
+    // on the runtime, has exported, so the test needs a multi-threaded runtime.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_with_periodic_reader() {
+        let exporter = RecordingExporter::default();
+        let provider = with_periodic_reader(SdkMeterProvider::builder(), exporter.clone()).build();
+        let counter = provider.meter("test").u64_counter("test_counter").build();
+        counter.add(1, &[]);
+
+        provider.force_flush().unwrap();
+        assert_eq!(*exporter.0.lock().unwrap(), vec!["test_counter"]);
+        provider.shutdown().unwrap();
+    }
+}
@@ -10,18 +10,100 @@ use opentelemetry_sdk::metrics::{
 };
 use opentelemetry_sdk::metrics::{MetricError, MetricResult};
 
-use opentelemetry::{otel_debug, otel_warn};
+use opentelemetry::{otel_debug, otel_warn, InstrumentationScope, Key, KeyValue, Value};
+use opentelemetry_sdk::Resource;
 
 use crate::tracepoint;
 use eventheader::_internal as ehi;
 use prost::Message;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 
 const MAX_EVENT_SIZE: usize = 65360;
 
+/// Resource attribute referencing the metadata event of a compact data event.
+const METADATA_ID: &str = "user_events.metadata_id";
+
 pub struct MetricsExporter {
     trace_point: Pin<Box<ehi::TracepointState>>,
+    metadata: Option<MetadataDeduplication>,
+    metric_filter: Option<MetricFilter>,
+}
+
+/// Predicate selecting the metrics to export, by instrumentation scope and metric name.
+type MetricFilter = Box<dyn Fn(&InstrumentationScope, &str) -> bool + Send + Sync>;
+
+/// State of the resource and scope deduplication.
+struct MetadataDeduplication {
+    /// Number of exports after which all metadata events are emitted again.
+    interval: u64,
+    export_count: AtomicU64,
+    state: Mutex<MetadataState>,
+}
+
+#[derive(Default)]
+struct MetadataState {
+    /// Resource the cached ids were computed for.
+    resource: Option<Resource>,
+    /// Metadata event ids by instrumentation scope.
+    ids: HashMap<InstrumentationScope, u64>,
+    /// Ids of the metadata events emitted in the current interval.
+    emitted: HashSet<u64>,
+}
+
+impl MetadataDeduplication {
+    fn new(interval: u32) -> Self {
+        MetadataDeduplication {
+            interval: u64::from(interval.max(1)),
+            export_count: AtomicU64::new(0),
+            state: Mutex::new(MetadataState::default()),
+        }
+    }
+
+    /// Starts an export, forgetting the emitted metadata events at the start of each interval.
+    fn start_export(&self) {
+        if self.export_count.fetch_add(1, Ordering::Relaxed) % self.interval == 0 {
+            self.state().emitted.clear();
+        }
+    }
+
+    /// Returns the metadata event id of the resource and scope, computing it only once.
+    fn id(&self, resource: &Resource, scope: &InstrumentationScope) -> u64 {
+        {
+            let state = self.state();
+            if state.resource.as_ref() == Some(resource) {
+                if let Some(id) = state.ids.get(scope) {
+                    return *id;
+                }
+            }
+        }
+
+        let id = metadata_id(resource, scope);
+        let mut state = self.state();
+        if state.resource.as_ref() != Some(resource) {
+            state.resource = Some(resource.clone());
+            state.ids.clear();
+        }
+        state.ids.insert(scope.clone(), id);
+        id
+    }
+
+    fn is_emitted(&self, id: u64) -> bool {
+        self.state().emitted.contains(&id)
+    }
+
+    fn set_emitted(&self, id: u64) {
+        self.state().emitted.insert(id);
+    }
+
+    fn state(&self) -> MutexGuard<'_, MetadataState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 }
 
 impl MetricsExporter {
@@ -32,8 +114,126 @@ impl MetricsExporter {
         unsafe {
             let _result = tracepoint::register(trace_point.as_ref());
         }
-        MetricsExporter { trace_point }
+        MetricsExporter {
+            trace_point,
+            metadata: None,
+            metric_filter: None,
+        }
+    }
+
+    /// Deduplicate the resource and instrumentation scope across exports.
+    ///
+    /// Instead of repeating the resource and scope in every event, a metadata event holding
+    /// them (and no metrics) is emitted, identified by the `user_events.metadata_id` resource
+    /// attribute. Data events then carry a resource with only this attribute and an empty
+    /// scope. Metadata events are emitted the first time a resource and scope pair is seen and
+    /// again every `interval` exports, so listeners attaching later can resolve the ids.
+    /// An `interval` of 0 is treated as 1.
+    pub fn with_metadata_deduplication(mut self, interval: u32) -> Self {
+        self.metadata = Some(MetadataDeduplication::new(interval));
+        self
+    }
+
+    /// Only export the metrics for which `filter` returns `true`.
+    ///
+    /// The filter is called with the instrumentation scope and the name of each metric before
+    /// it is serialized, so metrics not consumed by the local agent cost no tracepoint bandwidth.
+    /// Scopes without any exported metric are skipped entirely.
+    ///
+    /// ```no_run
+    /// use opentelemetry_user_events_metrics::MetricsExporter;
+    ///
+    /// let allowed = ["http.server.request.duration", "process.cpu.time"];
+    /// let exporter =
+    ///     MetricsExporter::new().with_metric_filter(move |_scope, name| allowed.contains(&name));
+    /// ```
+    pub fn with_metric_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&InstrumentationScope, &str) -> bool + Send + Sync + 'static,
+    {
+        self.metric_filter = Some(Box::new(filter));
+        self
     }
+
+    fn is_exported(&self, scope: &InstrumentationScope, metric_name: &str) -> bool {
+        self.metric_filter
+            .as_ref()
+            .map_or(true, |filter| filter(scope, metric_name))
+    }
+}
+
+/// Returns the id of the metadata event for the given resource and scope.
+///
+/// The id is a 64-bit FNV-1a hash of the resource and scope, with attributes sorted by key, so
+/// it is the same across exports, processes and Rust versions.
+fn metadata_id(resource: &Resource, scope: &InstrumentationScope) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write_str(resource.schema_url().unwrap_or_default());
+    hasher.write_attributes(resource.iter());
+    hasher.write_str(scope.name());
+    hasher.write_str(scope.version().unwrap_or_default());
+    hasher.write_str(scope.schema_url().unwrap_or_default());
+    hasher.write_attributes(scope.attributes().map(|kv| (&kv.key, &kv.value)));
+    hasher.0
+}
+
+/// 64-bit FNV-1a hasher. Unlike `DefaultHasher`, its output is specified and stable.
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// Writes the length-prefixed string, so that consecutive strings cannot be confused.
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+
+    fn write_attributes<'a>(&mut self, attributes: impl Iterator<Item = (&'a Key, &'a Value)>) {
+        let mut attributes: Vec<_> = attributes.collect();
+        attributes.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+        self.write(&(attributes.len() as u64).to_le_bytes());
+        for (key, value) in attributes {
+            self.write_str(key.as_str());
+            let kind: u8 = match value {
+                Value::Bool(_) => 0,
+                Value::I64(_) => 1,
+                Value::F64(_) => 2,
+                Value::String(_) => 3,
+                Value::Array(_) => 4,
+                _ => 5,
+            };
+            self.write(&[kind]);
+            self.write_str(&value.as_str());
+        }
+    }
+}
+
+/// Builds the metadata event with the given id for the given resource and scope.
+fn metadata_event(resource: &Resource, scope: &InstrumentationScope, id: u64) -> ResourceMetrics {
+    ResourceMetrics {
+        resource: resource.merge(&metadata_resource(id)),
+        scope_metrics: vec![ScopeMetrics {
+            scope: scope.clone(),
+            metrics: vec![],
+        }],
+    }
+}
+
+/// The resource of a data event referencing the metadata event with the given id.
+fn metadata_resource(id: u64) -> Resource {
+    Resource::new([KeyValue::new(METADATA_ID, format!("{:016x}", id))])
 }
 
 impl Default for MetricsExporter {
@@ -48,6 +248,63 @@ impl Debug for MetricsExporter {
     }
 }
 
+/// Encodes a [`ResourceMetrics`] holding a single data point into an OTLP
+/// `ExportMetricsServiceRequest`.
+///
+/// All fields of the data point are carried over by the proto conversion,
+/// including min/max of histograms and the scale, zero count and buckets of
+/// exponential histograms.
+fn encode_resource_metric(
+    resource_metric: &ResourceMetrics,
+    metric_name: &str,
+    metric_type: &str,
+) -> MetricResult<Vec<u8>> {
+    // Allocate a local buffer for each write operation
+    // TODO: Investigate if this can be optimized to avoid reallocation or
+    // allocate a fixed buffer size for all writes
+    let mut byte_array = Vec::new();
+
+    // Convert to proto message
+    let proto_message: ExportMetricsServiceRequest = resource_metric.into();
+    otel_debug!(name: "SerializeStart", 
+        metric_name = metric_name,
+        metric_type = metric_type);
+
+    // Encode directly into the buffer
+    match proto_message.encode(&mut byte_array) {
+        Ok(_) => {
+            otel_debug!(name: "SerializeSuccess", 
+                metric_name = metric_name,
+                metric_type = metric_type,
+                size = byte_array.len());
+        }
+        Err(err) => {
+            otel_debug!(name: "SerializeFailed",
+                error = err.to_string(),
+                metric_name = metric_name,
+                metric_type = metric_type,
+                size = byte_array.len());
+            return Err(MetricError::Other(err.to_string()));
+        }
+    }
+
+    // Check if the encoded message exceeds the 64 KB limit
+    if byte_array.len() > MAX_EVENT_SIZE {
+        otel_debug!(
+            name: "MaxEventSizeExceeded",
+            reason = format!("Encoded event size exceeds maximum allowed limit of {} bytes. Event will be dropped.", MAX_EVENT_SIZE),
+            metric_name = metric_name,
+            metric_type = metric_type,
+            size = byte_array.len()
+        );
+        return Err(MetricError::Other(
+            "Event size exceeds maximum allowed limit".into(),
+        ));
+    }
+
+    Ok(byte_array)
+}
+
 impl MetricsExporter {
     fn serialize_and_write(
         &self,
@@ -55,48 +312,7 @@ impl MetricsExporter {
         metric_name: &str,
         metric_type: &str,
     ) -> MetricResult<()> {
-        // Allocate a local buffer for each write operation
-        // TODO: Investigate if this can be optimized to avoid reallocation or
-        // allocate a fixed buffer size for all writes
-        let mut byte_array = Vec::new();
-
-        // Convert to proto message
-        let proto_message: ExportMetricsServiceRequest = resource_metric.into();
-        otel_debug!(name: "SerializeStart", 
-            metric_name = metric_name,
-            metric_type = metric_type);
-
-        // Encode directly into the buffer
-        match proto_message.encode(&mut byte_array) {
-            Ok(_) => {
-                otel_debug!(name: "SerializeSuccess", 
-                    metric_name = metric_name,
-                    metric_type = metric_type,
-                    size = byte_array.len());
-            }
-            Err(err) => {
-                otel_debug!(name: "SerializeFailed",
-                    error = err.to_string(),
-                    metric_name = metric_name,
-                    metric_type = metric_type,
-                    size = byte_array.len());
-                return Err(MetricError::Other(err.to_string()));
-            }
-        }
-
-        // Check if the encoded message exceeds the 64 KB limit
-        if byte_array.len() > MAX_EVENT_SIZE {
-            otel_debug!(
-                name: "MaxEventSizeExceeded",
-                reason = format!("Encoded event size exceeds maximum allowed limit of {} bytes. Event will be dropped.", MAX_EVENT_SIZE),
-                metric_name = metric_name,
-                metric_type = metric_type,
-                size = byte_array.len()
-            );
-            return Err(MetricError::Other(
-                "Event size exceeds maximum allowed limit".into(),
-            ));
-        }
+        let byte_array = encode_resource_metric(resource_metric, metric_name, metric_type)?;
 
         // Write to the tracepoint
         let result = tracepoint::write(&self.trace_point, &byte_array);
@@ -121,16 +337,59 @@ impl PushMetricExporter for MetricsExporter {
         if self.trace_point.enabled() {
             let mut errors = Vec::new();
 
+            // With metadata deduplication, every metadata event not yet emitted in the
+            // current interval is written ahead of the data events referencing it.
+            if let Some(metadata) = &self.metadata {
+                metadata.start_export();
+            }
+
             for scope_metric in &metrics.scope_metrics {
+                if !scope_metric
+                    .metrics
+                    .iter()
+                    .any(|metric| self.is_exported(&scope_metric.scope, &metric.name))
+                {
+                    continue;
+                }
+
+                let (resource, scope) = match &self.metadata {
+                    Some(metadata) => {
+                        let id = metadata.id(&metrics.resource, &scope_metric.scope);
+                        if !metadata.is_emitted(id) {
+                            let event = metadata_event(&metrics.resource, &scope_metric.scope, id);
+                            // Only a written metadata event can be referenced by later exports
+                            // without being emitted again.
+                            match encode_resource_metric(&event, "", "Metadata") {
+                                Ok(bytes) => match tracepoint::write(&self.trace_point, &bytes) {
+                                    0 => metadata.set_emitted(id),
+                                    result => errors.push(format!(
+                                        "Failed to write metadata event, error code {}",
+                                        result
+                                    )),
+                                },
+                                Err(e) => errors.push(e.to_string()),
+                            }
+                        }
+                        (
+                            metadata_resource(id),
+                            InstrumentationScope::builder("").build(),
+                        )
+                    }
+                    None => (metrics.resource.clone(), scope_metric.scope.clone()),
+                };
+
                 for metric in &scope_metric.metrics {
+                    if !self.is_exported(&scope_metric.scope, &metric.name) {
+                        continue;
+                    }
                     let data = &metric.data.as_any();
 
                     if let Some(histogram) = data.downcast_ref::<data::Histogram<u64>>() {
                         for data_point in &histogram.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -153,9 +412,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(histogram) = data.downcast_ref::<data::Histogram<f64>>() {
                         for data_point in &histogram.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -178,9 +437,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(gauge) = data.downcast_ref::<data::Gauge<u64>>() {
                         for data_point in &gauge.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -202,9 +461,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(gauge) = data.downcast_ref::<data::Gauge<i64>>() {
                         for data_point in &gauge.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -226,9 +485,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(gauge) = data.downcast_ref::<data::Gauge<f64>>() {
                         for data_point in &gauge.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -250,9 +509,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(sum) = data.downcast_ref::<data::Sum<u64>>() {
                         for data_point in &sum.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -274,9 +533,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(sum) = data.downcast_ref::<data::Sum<i64>>() {
                         for data_point in &sum.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -298,9 +557,9 @@ impl PushMetricExporter for MetricsExporter {
                     } else if let Some(sum) = data.downcast_ref::<data::Sum<f64>>() {
                         for data_point in &sum.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -324,9 +583,9 @@ impl PushMetricExporter for MetricsExporter {
                     {
                         for data_point in &exp_hist.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -377,9 +636,9 @@ impl PushMetricExporter for MetricsExporter {
                     {
                         for data_point in &exp_hist.data_points {
                             let resource_metric = ResourceMetrics {
-                                resource: metrics.resource.clone(),
+                                resource: resource.clone(),
                                 scope_metrics: vec![ScopeMetrics {
-                                    scope: scope_metric.scope.clone(),
+                                    scope: scope.clone(),
                                     metrics: vec![Metric {
                                         name: metric.name.clone(),
                                         description: metric.description.clone(),
@@ -456,3 +715,229 @@ impl PushMetricExporter for MetricsExporter {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_proto::tonic::metrics::v1::metric::Data;
+    use std::time::SystemTime;
+
+    fn resource_metrics(data: Box<dyn data::Aggregation>) -> ResourceMetrics {
+        ResourceMetrics {
+            resource: Resource::new(vec![KeyValue::new("service.name", "test")]),
+            scope_metrics: vec![ScopeMetrics {
+                scope: InstrumentationScope::builder("test").build(),
+                metrics: vec![Metric {
+                    name: "test_metric".into(),
+                    description: "".into(),
+                    unit: "".into(),
+                    data,
+                }],
+            }],
+        }
+    }
+
+    fn decode_data(bytes: &[u8]) -> Data {
+        let request = ExportMetricsServiceRequest::decode(bytes).unwrap();
+        request.resource_metrics[0].scope_metrics[0].metrics[0]
+            .data
+            .clone()
+            .unwrap()
+    }
+
+    #[test]
+    fn exponential_histogram_round_trip() {
+        let resource_metric = resource_metrics(Box::new(data::ExponentialHistogram {
+            temporality: Temporality::Delta,
+            data_points: vec![ExponentialHistogramDataPoint {
+                attributes: vec![KeyValue::new("key", "value")],
+                start_time: SystemTime::UNIX_EPOCH,
+                time: SystemTime::now(),
+                count: 7,
+                min: Some(-4.0),
+                max: Some(12.5),
+                sum: 20.0,
+                scale: 3,
+                zero_count: 2,
+                positive_bucket: ExponentialBucket {
+                    offset: 1,
+                    counts: vec![1, 0, 2],
+                },
+                negative_bucket: ExponentialBucket {
+                    offset: -2,
+                    counts: vec![2],
+                },
+                zero_threshold: 0.5,
+                exemplars: vec![],
+            }],
+        }));
+
+        let bytes =
+            encode_resource_metric(&resource_metric, "test_metric", "ExponentialHistogram<f64>")
+                .unwrap();
+
+        let Data::ExponentialHistogram(histogram) = decode_data(&bytes) else {
+            panic!("expected an exponential histogram");
+        };
+        let data_point = &histogram.data_points[0];
+        assert_eq!(data_point.count, 7);
+        assert_eq!(data_point.sum, Some(20.0));
+        assert_eq!(data_point.min, Some(-4.0));
+        assert_eq!(data_point.max, Some(12.5));
+        assert_eq!(data_point.scale, 3);
+        assert_eq!(data_point.zero_count, 2);
+        assert_eq!(data_point.zero_threshold, 0.5);
+        let positive = data_point.positive.as_ref().unwrap();
+        assert_eq!(positive.offset, 1);
+        assert_eq!(positive.bucket_counts, vec![1, 0, 2]);
+        let negative = data_point.negative.as_ref().unwrap();
+        assert_eq!(negative.offset, -2);
+        assert_eq!(negative.bucket_counts, vec![2]);
+    }
+
+    #[test]
+    fn histogram_round_trip_keeps_min_max() {
+        let resource_metric = resource_metrics(Box::new(data::Histogram {
+            temporality: Temporality::Delta,
+            data_points: vec![data::HistogramDataPoint {
+                attributes: vec![],
+                start_time: SystemTime::UNIX_EPOCH,
+                time: SystemTime::now(),
+                count: 3,
+                bounds: vec![1.0, 10.0],
+                bucket_counts: vec![1, 1, 1],
+                min: Some(0u64),
+                max: Some(42u64),
+                sum: 47,
+                exemplars: vec![],
+            }],
+        }));
+
+        let bytes =
+            encode_resource_metric(&resource_metric, "test_metric", "Histogram<u64>").unwrap();
+
+        let Data::Histogram(histogram) = decode_data(&bytes) else {
+            panic!("expected a histogram");
+        };
+        let data_point = &histogram.data_points[0];
+        assert_eq!(data_point.count, 3);
+        assert_eq!(data_point.sum, Some(47.0));
+        assert_eq!(data_point.min, Some(0.0));
+        assert_eq!(data_point.max, Some(42.0));
+        assert_eq!(data_point.explicit_bounds, vec![1.0, 10.0]);
+        assert_eq!(data_point.bucket_counts, vec![1, 1, 1]);
+    }
+
+    #[test]
+    fn metadata_event_carries_resource_and_scope() {
+        let resource = Resource::new(vec![KeyValue::new("service.name", "test")]);
+        let scope = InstrumentationScope::builder("test")
+            .with_version("1.0")
+            .build();
+
+        let id = metadata_id(&resource, &scope);
+        assert_eq!(metadata_id(&resource, &scope), id);
+        let other_scope = InstrumentationScope::builder("other").build();
+        assert_ne!(metadata_id(&resource, &other_scope), id);
+
+        let metadata = metadata_event(&resource, &scope, id);
+
+        let bytes = encode_resource_metric(&metadata, "", "Metadata").unwrap();
+        let request = ExportMetricsServiceRequest::decode(bytes.as_slice()).unwrap();
+        let resource_metrics = &request.resource_metrics[0];
+        let attributes = &resource_metrics.resource.as_ref().unwrap().attributes;
+        assert!(attributes.iter().any(|kv| kv.key == "service.name"));
+        assert!(attributes.iter().any(|kv| kv.key == METADATA_ID));
+        let scope_metrics = &resource_metrics.scope_metrics[0];
+        assert_eq!(scope_metrics.scope.as_ref().unwrap().name, "test");
+        assert_eq!(scope_metrics.scope.as_ref().unwrap().version, "1.0");
+        assert!(scope_metrics.metrics.is_empty());
+
+        assert_eq!(
+            metadata_resource(id).get(opentelemetry::Key::from_static_str(METADATA_ID)),
+            Some(format!("{:016x}", id).into())
+        );
+    }
+
+    #[test]
+    fn metadata_id_is_stable() {
+        let resource = Resource::new(vec![
+            KeyValue::new("service.name", "test"),
+            KeyValue::new("host.name", "host"),
+        ]);
+        let reordered = Resource::new(vec![
+            KeyValue::new("host.name", "host"),
+            KeyValue::new("service.name", "test"),
+        ]);
+        let scope = InstrumentationScope::builder("test")
+            .with_version("1.0")
+            .build();
+
+        assert_eq!(
+            metadata_id(&resource, &scope),
+            metadata_id(&reordered, &scope)
+        );
+        // The id must not change across Rust versions or processes.
+        assert_eq!(metadata_id(&resource, &scope), 0x1447_e8f4_a656_1893);
+
+        let typed = Resource::new(vec![
+            KeyValue::new("service.name", "test"),
+            KeyValue::new("host.name", 1),
+        ]);
+        let stringly = Resource::new(vec![
+            KeyValue::new("service.name", "test"),
+            KeyValue::new("host.name", "1"),
+        ]);
+        assert_ne!(metadata_id(&typed, &scope), metadata_id(&stringly, &scope));
+    }
+
+    #[test]
+    fn metadata_ids_are_cached() {
+        let resource = Resource::new(vec![KeyValue::new("service.name", "test")]);
+        let scope = InstrumentationScope::builder("test").build();
+        let other_scope = InstrumentationScope::builder("other").build();
+        let metadata = MetadataDeduplication::new(2);
+
+        let id = metadata.id(&resource, &scope);
+        assert_eq!(id, metadata_id(&resource, &scope));
+        assert_eq!(metadata.id(&resource, &scope), id);
+        metadata.id(&resource, &other_scope);
+        assert_eq!(metadata.state().ids.len(), 2);
+
+        // A different resource replaces the cached ids.
+        let other_resource = Resource::new(vec![KeyValue::new("service.name", "other")]);
+        assert_ne!(metadata.id(&other_resource, &scope), id);
+        assert_eq!(metadata.state().ids.len(), 1);
+    }
+
+    #[test]
+    fn metadata_emitted_once_per_interval() {
+        let metadata = MetadataDeduplication::new(2);
+        metadata.start_export();
+        assert!(!metadata.is_emitted(1));
+        metadata.set_emitted(1);
+        assert!(metadata.is_emitted(1));
+
+        metadata.start_export();
+        assert!(metadata.is_emitted(1));
+
+        metadata.start_export();
+        assert!(!metadata.is_emitted(1));
+    }
+
+    #[test]
+    fn metric_filter() {
+        let scope = InstrumentationScope::builder("test").build();
+        let exporter = MetricsExporter::new();
+        assert!(exporter.is_exported(&scope, "anything"));
+
+        let exporter = MetricsExporter::new()
+            .with_metric_filter(|scope, name| scope.name() == "test" && name.starts_with("http."));
+        assert!(exporter.is_exported(&scope, "http.server.request.duration"));
+        assert!(!exporter.is_exported(&scope, "process.cpu.time"));
+        assert!(!exporter.is_exported(
+            &InstrumentationScope::builder("other").build(),
+            "http.server.request.duration"
+        ));
+    }
+}
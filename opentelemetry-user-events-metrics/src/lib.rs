@@ -1,4 +1,6 @@
+mod builder_ext;
 mod exporter;
 mod tracepoint;
 
+pub use builder_ext::UserEventsMeterProviderBuilderExt;
 pub use exporter::MetricsExporter;
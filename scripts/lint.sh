@@ -15,6 +15,8 @@ if rustup component add clippy; then
     -Dwarnings
 
   cargo_feature opentelemetry-aws "default"
+  cargo_feature opentelemetry-aws "xray-exporter"
+  cargo_feature opentelemetry-aws "emf-exporter"
 
   cargo_feature opentelemetry-datadog "reqwest-blocking-client,intern-std"
   cargo_feature opentelemetry-datadog "reqwest-client,intern-std"
@@ -42,4 +44,13 @@ if rustup component add clippy; then
   cargo_feature opentelemetry-user-events-metrics ""
 
   cargo_feature opentelemetry-resource-detectors ""
+  cargo_feature opentelemetry-resource-detectors "default"
+  cargo_feature opentelemetry-resource-detectors "host"
+  cargo_feature opentelemetry-resource-detectors "orchestrator"
+  cargo_feature opentelemetry-resource-detectors "os"
+  cargo_feature opentelemetry-resource-detectors "process"
+  cargo_feature opentelemetry-resource-detectors "telemetry-distro"
+  cargo_feature opentelemetry-resource-detectors "rt-tokio"
+
+  cargo_feature opentelemetry-instrumentation-kafka ""
 fi
@@ -31,6 +31,7 @@ fn init_logger() -> LoggerProvider {
     let exporter_config = ExporterConfig {
         default_keyword: 1,
         keywords_map: HashMap::new(),
+        ..Default::default()
     };
     let reenterant_processor = ReentrantLogProcessor::new(
         "my-provider-name",
@@ -35,6 +35,7 @@ fn init_logger() -> LoggerProvider {
     let exporter_config = ExporterConfig {
         default_keyword: 1,
         keywords_map: HashMap::new(),
+        ..Default::default()
     };
     let exporter = UserEventsExporter::new("testprovider", None, exporter_config);
     let reentrant_processor = ReentrantLogProcessor::new(exporter);